@@ -38,6 +38,7 @@
 //!
 //! [Advent of Code 2020 - Day 1](https://adventofcode.com/2020/day/1)
 
+use hashbrown::HashSet;
 use std::str::FromStr;
 
 #[aoc_generator(day1)]
@@ -55,62 +56,160 @@ pub fn parse(input: &str) -> Vec<i32> {
         .collect()
 }
 
-pub fn find_two_incorrect_expenses(expense_report: &[i32]) -> (i32, i32) {
-    expense_report
-        .iter()
-        .copied()
-        .enumerate()
-        .flat_map(|(i, e1)| {
-            let needed = 2020 - e1;
-            expense_report.iter().copied().skip(i + 1).find_map(|e2| {
-                if e2 == needed {
-                    Some((e1, e2))
-                } else {
-                    None
+/// Finds all subsets of `k` entries of the expense report that sum up to the
+/// target.
+///
+/// Each entry is used at most once per subset. Subsets are returned with their
+/// entries in ascending order, and subsets with equal entries are returned only
+/// once. The subsets are sorted.
+pub fn find_k_expenses(expense_report: &[i32], k: usize, target: i32) -> Vec<Vec<i32>> {
+    let mut subsets = Vec::new();
+    search_k_expenses(expense_report, k, target, &mut |subset| {
+        subsets.push(subset);
+        true
+    });
+    if k == 2 {
+        subsets.sort_unstable();
+    }
+    subsets
+}
+
+/// Finds the first subset of `k` entries of the expense report that sums up
+/// to the target.
+///
+/// For `k = 2` this is the pair completed first when scanning the report in
+/// order, otherwise the lowest subset in lexicographic order.
+pub fn find_first_k_expenses(expense_report: &[i32], k: usize, target: i32) -> Option<Vec<i32>> {
+    let mut first = None;
+    search_k_expenses(expense_report, k, target, &mut |subset| {
+        first = Some(subset);
+        false
+    });
+    first
+}
+
+/// Calls `found` for every subset summing up to the target until it returns
+/// `false`.
+fn search_k_expenses(
+    expense_report: &[i32],
+    k: usize,
+    target: i32,
+    found: &mut impl FnMut(Vec<i32>) -> bool,
+) {
+    let target = i64::from(target);
+    match k {
+        0 => {
+            if target == 0 {
+                found(Vec::new());
+            }
+        },
+        1 => {
+            if let Some(&entry) = expense_report.iter().find(|&&e| i64::from(e) == target) {
+                found(vec![entry]);
+            }
+        },
+        2 => search_pairs_by_hashing(expense_report, target, found),
+        _ => {
+            let mut sorted = expense_report.to_vec();
+            sorted.sort_unstable();
+            let mut prefix = Vec::with_capacity(k);
+            search_sorted(&sorted, k, target, &mut prefix, found);
+        },
+    }
+}
+
+fn search_pairs_by_hashing(
+    expense_report: &[i32],
+    target: i64,
+    found: &mut impl FnMut(Vec<i32>) -> bool,
+) {
+    let mut seen = HashSet::with_capacity(expense_report.len());
+    let mut reported = HashSet::new();
+    for &entry in expense_report {
+        let needed = target - i64::from(entry);
+        if seen.contains(&needed) {
+            let pair = (entry.min(needed as i32), entry.max(needed as i32));
+            if reported.insert(pair) && !found(vec![pair.0, pair.1]) {
+                return;
+            }
+        }
+        seen.insert(i64::from(entry));
+    }
+}
+
+/// Fixes the lowest entry of the subset and recurses until two entries are
+/// left, which are searched for with two pointers from both ends. Returns
+/// `false` if the search has been stopped.
+fn search_sorted(
+    sorted: &[i32],
+    k: usize,
+    target: i64,
+    prefix: &mut Vec<i32>,
+    found: &mut impl FnMut(Vec<i32>) -> bool,
+) -> bool {
+    if sorted.len() < k {
+        return true;
+    }
+    if k == 2 {
+        let (mut lo, mut hi) = (0, sorted.len() - 1);
+        while lo < hi {
+            let sum = i64::from(sorted[lo]) + i64::from(sorted[hi]);
+            if sum < target {
+                lo += 1;
+            } else if sum > target {
+                hi -= 1;
+            } else {
+                let mut subset = prefix.clone();
+                subset.extend([sorted[lo], sorted[hi]]);
+                if !found(subset) {
+                    return false;
                 }
-            })
-        })
-        .next()
-        .expect("no result found!")
+                let (low, high) = (sorted[lo], sorted[hi]);
+                while lo < hi && sorted[lo] == low {
+                    lo += 1;
+                }
+                while lo < hi && sorted[hi] == high {
+                    hi -= 1;
+                }
+            }
+        }
+        return true;
+    }
+    for index in 0..=sorted.len() - k {
+        if index > 0 && sorted[index] == sorted[index - 1] {
+            continue;
+        }
+        prefix.push(sorted[index]);
+        let proceed = search_sorted(
+            &sorted[index + 1..],
+            k - 1,
+            target - i64::from(sorted[index]),
+            prefix,
+            found,
+        );
+        prefix.pop();
+        if !proceed {
+            return false;
+        }
+    }
+    true
 }
 
-pub fn find_three_incorrect_expenses(expense_report: &[i32]) -> (i32, i32, i32) {
-    expense_report
-        .iter()
-        .copied()
-        .enumerate()
-        .flat_map(|(i1, e1)| {
-            let needed1 = 2020 - e1;
-            expense_report
-                .iter()
-                .copied()
-                .enumerate()
-                .skip(i1 + 1)
-                .flat_map(move |(i2, e2)| {
-                    let needed2 = needed1 - e2;
-                    expense_report.iter().copied().skip(i2 + 1).find_map(|e3| {
-                        if e3 == needed2 {
-                            Some((e1, e2, e3))
-                        } else {
-                            None
-                        }
-                    })
-                })
-        })
-        .next()
+fn product_of_k_incorrect_expenses(expense_report: &[i32], k: usize) -> i32 {
+    find_first_k_expenses(expense_report, k, 2020)
         .expect("no result found!")
+        .iter()
+        .product()
 }
 
 #[aoc(day1, part1)]
 pub fn product_of_two_incorrect_expenses(expense_report: &[i32]) -> i32 {
-    let (e1, e2) = find_two_incorrect_expenses(expense_report);
-    e1 * e2
+    product_of_k_incorrect_expenses(expense_report, 2)
 }
 
 #[aoc(day1, part2)]
 pub fn product_of_three_incorrect_expenses(expense_report: &[i32]) -> i32 {
-    let (e1, e2, e3) = find_three_incorrect_expenses(expense_report);
-    e1 * e2 * e3
+    product_of_k_incorrect_expenses(expense_report, 3)
 }
 
 #[cfg(test)]
@@ -28,6 +28,78 @@ mod product_of_three_incorrect_expenses {
     }
 }
 
+mod find_k_expenses {
+    use super::*;
+
+    #[test]
+    fn pairs_in_example1() {
+        let report = vec![1721, 979, 366, 299, 675, 1456];
+
+        let subsets = find_k_expenses(&report, 2, 2020);
+
+        assert_eq!(subsets, vec![vec![299, 1721]]);
+    }
+
+    #[test]
+    fn triples_in_example1() {
+        let report = vec![1721, 979, 366, 299, 675, 1456];
+
+        let subsets = find_k_expenses(&report, 3, 2020);
+
+        assert_eq!(subsets, vec![vec![366, 675, 979]]);
+    }
+
+    #[test]
+    fn all_subsets_of_any_size_and_target() {
+        let report = vec![1, 2, 3, 4, 5, 6];
+
+        assert_eq!(
+            find_k_expenses(&report, 2, 7),
+            vec![vec![1, 6], vec![2, 5], vec![3, 4]]
+        );
+        assert_eq!(
+            find_k_expenses(&report, 3, 10),
+            vec![vec![1, 3, 6], vec![1, 4, 5], vec![2, 3, 5]]
+        );
+        assert_eq!(
+            find_k_expenses(&report, 4, 14),
+            vec![vec![1, 2, 5, 6], vec![1, 3, 4, 6], vec![2, 3, 4, 5]]
+        );
+        assert_eq!(
+            find_k_expenses(&report, 6, 21),
+            vec![vec![1, 2, 3, 4, 5, 6]]
+        );
+        assert_eq!(find_k_expenses(&report, 1, 4), vec![vec![4]]);
+        assert_eq!(find_k_expenses(&report, 0, 0), vec![Vec::<i32>::new()]);
+        assert!(find_k_expenses(&report, 7, 21).is_empty());
+        assert!(find_k_expenses(&report, 3, 100).is_empty());
+    }
+
+    #[test]
+    fn equal_entries_are_used_at_most_once_and_reported_once() {
+        let report = vec![5, 5, 5, -5, 0, 10];
+
+        assert_eq!(
+            find_k_expenses(&report, 2, 10),
+            vec![vec![0, 10], vec![5, 5]]
+        );
+        assert_eq!(
+            find_k_expenses(&report, 3, 15),
+            vec![vec![0, 5, 10], vec![5, 5, 5]]
+        );
+        assert!(find_k_expenses(&report, 2, 20).is_empty());
+    }
+
+    #[test]
+    fn first_subset() {
+        let report = vec![2, 6, 5, 1, 3, 4];
+
+        assert_eq!(find_first_k_expenses(&report, 2, 7), Some(vec![2, 5]));
+        assert_eq!(find_first_k_expenses(&report, 3, 10), Some(vec![1, 3, 6]));
+        assert_eq!(find_first_k_expenses(&report, 3, 100), None);
+    }
+}
+
 mod part1 {
     use super::*;
 
@@ -129,9 +129,11 @@
 //!
 //! [Advent of Code 2020 - Day 8](https://adventofcode.com/2020/day/8)
 
-use std::collections::HashSet;
+use hashbrown::HashMap;
+use std::fmt;
+use std::fmt::Display;
 use std::iter::FromIterator;
-use std::ops::Index;
+use std::ops::{Index, IndexMut};
 use std::slice::Iter;
 use std::str::FromStr;
 
@@ -153,6 +155,13 @@ impl Program {
     pub fn instructions(&self) -> Iter<'_, Instruction> {
         self.0.iter()
     }
+
+    /// Whether the control flow depends on the values of registers.
+    pub fn has_conditional_jumps(&self) -> bool {
+        self.0
+            .iter()
+            .any(|inst| matches!(inst.operation, OperationCode::Jz(_) | OperationCode::Jnz(_)))
+    }
 }
 
 impl Index<usize> for Program {
@@ -163,22 +172,97 @@ impl Index<usize> for Program {
     }
 }
 
+/// An instruction of the handheld assembly.
+///
+/// The `argument` is the immediate value or the jump offset of the operation.
+/// Operations that take register operands carry them in the operation code.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Instruction {
     pub operation: OperationCode,
     pub argument: Data,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Register {
+    Acc,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl FromStr for Register {
+    type Err = OperationCodeParseError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "acc" => Ok(Register::Acc),
+            "a" => Ok(Register::A),
+            "b" => Ok(Register::B),
+            "c" => Ok(Register::C),
+            "d" => Ok(Register::D),
+            _ => Err(OperationCodeParseError::InvalidRegister(name.into())),
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Register::Acc => "acc",
+            Register::A => "a",
+            Register::B => "b",
+            Register::C => "c",
+            Register::D => "d",
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OperationCode {
+    /// `acc <value>`: adds the value to the accumulator
     Acc,
+    /// `jmp <offset>`: jumps relative to the current instruction
     Jmp,
+    /// `nop <value>`: does nothing
     Nop,
+    /// `set <r> <value>`: sets register r to the value
+    SetI(Register),
+    /// `set <r> <s>`: sets register r to the value of register s
+    SetR(Register, Register),
+    /// `add <r> <value>`: adds the value to register r
+    AddI(Register),
+    /// `add <r> <s>`: adds the value of register s to register r
+    AddR(Register, Register),
+    /// `mul <r> <value>`: multiplies register r by the value
+    MulI(Register),
+    /// `mul <r> <s>`: multiplies register r by the value of register s
+    MulR(Register, Register),
+    /// `jz <r> <offset>`: jumps relative to the current instruction if
+    /// register r is zero
+    Jz(Register),
+    /// `jnz <r> <offset>`: jumps relative to the current instruction if
+    /// register r is not zero
+    Jnz(Register),
+    /// `out <r>`: appends the value of register r to the output
+    Out(Register),
+}
+
+impl OperationCode {
+    /// Returns the operation that replaces this one when repairing a program.
+    pub fn flipped(self) -> Option<OperationCode> {
+        match self {
+            OperationCode::Jmp => Some(OperationCode::Nop),
+            OperationCode::Nop => Some(OperationCode::Jmp),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OperationCodeParseError {
     InvalidOperationCode(String),
+    InvalidRegister(String),
 }
 
 impl FromStr for OperationCode {
@@ -194,24 +278,80 @@ impl FromStr for OperationCode {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct Registers {
     pub pc: Address,
     pub acc: Data,
+    /// The general purpose registers `a`, `b`, `c` and `d`
+    pub gp: [Data; 4],
 }
 
-impl Default for Registers {
-    fn default() -> Self {
-        Self { pc: 0, acc: 0 }
+impl Index<Register> for Registers {
+    type Output = Data;
+
+    fn index(&self, register: Register) -> &Self::Output {
+        match register {
+            Register::Acc => &self.acc,
+            Register::A => &self.gp[0],
+            Register::B => &self.gp[1],
+            Register::C => &self.gp[2],
+            Register::D => &self.gp[3],
+        }
+    }
+}
+
+impl IndexMut<Register> for Registers {
+    fn index_mut(&mut self, register: Register) -> &mut Self::Output {
+        match register {
+            Register::Acc => &mut self.acc,
+            Register::A => &mut self.gp[0],
+            Register::B => &mut self.gp[1],
+            Register::C => &mut self.gp[2],
+            Register::D => &mut self.gp[3],
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Continuation {
     Continue,
+    /// The executed instruction produced an output value.
+    Output(Data),
     Halt,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// The program counter left the program.
+    Halted,
+    /// The program entered an endless loop: the instruction at `address` was
+    /// about to be executed a second time with the same registers, or at all
+    /// if the program has no conditional jumps. It was first executed that
+    /// way in step `first_step` of the trace.
+    Revisited { address: Address, first_step: usize },
+    /// The maximum number of steps has been executed.
+    StepLimit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    /// The registers before each executed instruction in order of execution.
+    pub states: Vec<Registers>,
+    /// The registers when the execution stopped.
+    pub last: Registers,
+    /// The values produced by `out` instructions.
+    pub output: Vec<Data>,
+    pub exit: Exit,
+}
+
+/// Returns the address `offset` instructions away from `address`. Jumping
+/// before the first instruction leaves the program.
+fn jump(address: Address, offset: Data) -> Address {
+    address
+        .checked_add_signed(offset as isize)
+        .unwrap_or(Address::MAX)
+}
+
 #[derive(Debug)]
 pub struct VirtualMachine {
     program: Program,
@@ -226,113 +366,485 @@ impl VirtualMachine {
         self.program.0[address].operation = opcode;
     }
 
+    /// Executes the instruction at the program counter. Arithmetic wraps
+    /// around on overflow.
     pub fn step(&self, reg: &mut Registers) -> Continuation {
         if reg.pc >= self.program.len() {
             return Continuation::Halt;
         }
         let instruction = self.program[reg.pc];
-        match instruction.operation {
+        let argument = instruction.argument;
+        let mut continuation = Continuation::Continue;
+        reg.pc = match instruction.operation {
             OperationCode::Acc => {
-                reg.acc += instruction.argument;
-                reg.pc += 1;
+                reg.acc = reg.acc.wrapping_add(argument);
+                reg.pc + 1
+            },
+            OperationCode::Jmp => jump(reg.pc, argument),
+            OperationCode::Nop => reg.pc + 1,
+            OperationCode::SetI(r) => {
+                reg[r] = argument;
+                reg.pc + 1
+            },
+            OperationCode::SetR(r, s) => {
+                reg[r] = reg[s];
+                reg.pc + 1
+            },
+            OperationCode::AddI(r) => {
+                reg[r] = reg[r].wrapping_add(argument);
+                reg.pc + 1
+            },
+            OperationCode::AddR(r, s) => {
+                reg[r] = reg[r].wrapping_add(reg[s]);
+                reg.pc + 1
+            },
+            OperationCode::MulI(r) => {
+                reg[r] = reg[r].wrapping_mul(argument);
+                reg.pc + 1
+            },
+            OperationCode::MulR(r, s) => {
+                reg[r] = reg[r].wrapping_mul(reg[s]);
+                reg.pc + 1
+            },
+            OperationCode::Jz(r) if reg[r] == 0 => jump(reg.pc, argument),
+            OperationCode::Jnz(r) if reg[r] != 0 => jump(reg.pc, argument),
+            OperationCode::Jz(_) | OperationCode::Jnz(_) => reg.pc + 1,
+            OperationCode::Out(r) => {
+                continuation = Continuation::Output(reg[r]);
+                reg.pc + 1
+            },
+        };
+        continuation
+    }
+
+    fn execute(
+        &self,
+        registers: Registers,
+        mut stop: impl FnMut(&Registers, usize) -> Option<Exit>,
+    ) -> Trace {
+        let mut reg = registers;
+        let mut states = Vec::new();
+        let mut output = Vec::new();
+        let exit = loop {
+            if let Some(exit) = stop(&reg, states.len()) {
+                break exit;
             }
-            OperationCode::Jmp => {
-                if instruction.argument < 0 {
-                    reg.pc -= instruction.argument.abs() as usize
+            let before = reg;
+            match self.step(&mut reg) {
+                Continuation::Continue => {},
+                Continuation::Output(value) => output.push(value),
+                Continuation::Halt => break Exit::Halted,
+            }
+            states.push(before);
+        };
+        Trace {
+            states,
+            last: reg,
+            output,
+            exit,
+        }
+    }
+
+    /// Runs the program until it halts, enters an endless loop or
+    /// `max_steps` instructions have been executed.
+    ///
+    /// Without conditional jumps the program loops as soon as an instruction
+    /// is about to be executed for the second time, which happens within as
+    /// many steps as the program has instructions. Otherwise it loops once
+    /// all registers are the same as before, which may take as many steps as
+    /// the registers have states, so `max_steps` bounds the states remembered.
+    pub fn trace(&self, registers: Registers, max_steps: usize) -> Trace {
+        if self.program.has_conditional_jumps() {
+            let mut first_steps = HashMap::new();
+            return self.execute(registers, |reg, step| {
+                if reg.pc >= self.program.len() {
+                    return None;
+                }
+                let first_step = *first_steps.entry(*reg).or_insert(step);
+                if first_step != step {
+                    Some(Exit::Revisited {
+                        address: reg.pc,
+                        first_step,
+                    })
+                } else if step >= max_steps {
+                    Some(Exit::StepLimit)
                 } else {
-                    reg.pc += instruction.argument as usize
+                    None
+                }
+            });
+        }
+        let mut first_steps = vec![None; self.program.len()];
+        self.execute(registers, |reg, step| {
+            let first_step = first_steps.get_mut(reg.pc)?;
+            match *first_step {
+                Some(first_step) => Some(Exit::Revisited {
+                    address: reg.pc,
+                    first_step,
+                }),
+                None if step >= max_steps => Some(Exit::StepLimit),
+                None => {
+                    *first_step = Some(step);
+                    None
+                },
+            }
+        })
+    }
+
+    /// Runs the program until it halts or `max_steps` instructions have been
+    /// executed.
+    pub fn run(&self, registers: Registers, max_steps: usize) -> Trace {
+        self.execute(registers, |_, step| {
+            if step >= max_steps {
+                Some(Exit::StepLimit)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Patch {
+    pub address: Address,
+    pub operation: OperationCode,
+}
+
+/// Returns the addresses that may be executed after the instruction at
+/// `address`. `None` stands for leaving the program.
+fn next_addresses(
+    address: Address,
+    operation: OperationCode,
+    argument: Data,
+    len: usize,
+) -> Vec<Option<Address>> {
+    let within = |next: Address| if next < len { Some(next) } else { None };
+    match operation {
+        OperationCode::Jmp => vec![within(jump(address, argument))],
+        OperationCode::Jz(_) | OperationCode::Jnz(_) => {
+            vec![within(address + 1), within(jump(address, argument))]
+        },
+        _ => vec![within(address + 1)],
+    }
+}
+
+/// The graph of instructions in a program, where each instruction points to
+/// the instructions that may be executed after it.
+///
+/// The set of instructions from which the program can terminate is found by a
+/// single walk of the reversed graph starting at the exit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    program: Program,
+    successors: Vec<Vec<Option<Address>>>,
+    terminating: Vec<bool>,
+}
+
+impl ControlFlowGraph {
+    pub fn analyze(program: &Program) -> Self {
+        let len = program.len();
+        let successors = program
+            .instructions()
+            .enumerate()
+            .map(|(address, inst)| next_addresses(address, inst.operation, inst.argument, len))
+            .collect::<Vec<_>>();
+
+        let mut predecessors = vec![Vec::new(); len];
+        let mut open = Vec::new();
+        for (address, next_addresses) in successors.iter().enumerate() {
+            for successor in next_addresses {
+                match successor {
+                    Some(next) => predecessors[*next].push(address),
+                    None => open.push(address),
                 }
             }
-            OperationCode::Nop => reg.pc += 1,
         }
-        Continuation::Continue
+        let mut terminating = vec![false; len];
+        while let Some(address) = open.pop() {
+            if !terminating[address] {
+                terminating[address] = true;
+                open.extend(predecessors[address].iter().copied());
+            }
+        }
+
+        Self {
+            program: program.clone(),
+            successors,
+            terminating,
+        }
+    }
+
+    pub fn successors(&self, address: Address) -> &[Option<Address>] {
+        &self.successors[address]
+    }
+
+    /// Whether the program can terminate when started at the given address.
+    /// With conditional jumps this only says that some path from the address
+    /// leaves the program.
+    pub fn terminates_from(&self, address: Address) -> bool {
+        self.terminating[address]
+    }
+
+    /// Finds all single instruction flips between `jmp` and `nop` on the
+    /// executed path that make the program terminate.
+    ///
+    /// Returns `None` if the program has conditional jumps, as then the
+    /// graph can not tell whether the program terminates.
+    pub fn repairs(&self) -> Option<Vec<Patch>> {
+        if self.program.has_conditional_jumps() {
+            return None;
+        }
+        let len = self.program.len();
+        let vm = VirtualMachine::new(self.program.clone());
+        let repairs = vm
+            .trace(Registers::default(), len)
+            .states
+            .iter()
+            .filter_map(|reg| {
+                let instruction = self.program[reg.pc];
+                let operation = instruction.operation.flipped()?;
+                next_addresses(reg.pc, operation, instruction.argument, len)
+                    .into_iter()
+                    .all(|successor| successor.is_none_or(|next| self.terminating[next]))
+                    .then_some(Patch {
+                        address: reg.pc,
+                        operation,
+                    })
+            })
+            .collect();
+        Some(repairs)
     }
 }
 
-#[aoc_generator(day8)]
-pub fn parse_program(input: &str) -> Program {
-    let mut instructions = Vec::with_capacity(input.len() / 8);
-    for (lno, line) in input.lines().enumerate() {
-        if line.is_empty() {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleError {
+    InvalidOperationCode {
+        line: usize,
+        opcode: String,
+    },
+    InvalidRegister {
+        line: usize,
+        name: String,
+    },
+    InvalidArgument {
+        line: usize,
+        argument: String,
+    },
+    MissingOperand {
+        line: usize,
+    },
+    UnexpectedOperand {
+        line: usize,
+        operand: String,
+    },
+    DuplicateLabel {
+        line: usize,
+        label: String,
+    },
+    UndefinedLabel {
+        line: usize,
+        label: String,
+    },
+    /// Labels must not be empty or named like a register.
+    InvalidLabel {
+        line: usize,
+        label: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand<'a> {
+    Register(Register),
+    Value(Data),
+    Label(&'a str),
+}
+
+impl Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Register(register) => write!(f, "{}", register),
+            Operand::Value(value) => write!(f, "{}", value),
+            Operand::Label(label) => f.write_str(label),
+        }
+    }
+}
+
+fn parse_operand(line: usize, operand: &str) -> Result<Operand<'_>, AssembleError> {
+    if let Ok(register) = Register::from_str(operand) {
+        Ok(Operand::Register(register))
+    } else if operand.starts_with(|c: char| c == '+' || c == '-' || c.is_ascii_digit()) {
+        Data::from_str(operand)
+            .map(Operand::Value)
+            .map_err(|_| AssembleError::InvalidArgument {
+                line,
+                argument: operand.into(),
+            })
+    } else if operand
+        .chars()
+        .all(|c| c == '_' || c.is_ascii_alphanumeric())
+    {
+        Ok(Operand::Label(operand))
+    } else {
+        Err(AssembleError::InvalidArgument {
+            line,
+            argument: operand.into(),
+        })
+    }
+}
+
+/// Assembles the source code of a handheld program.
+///
+/// Each line holds one instruction, optionally preceded by a label of the form
+/// `name:`. Everything after a `;` is a comment. Jump offsets are given either
+/// as a number relative to the jump instruction or as a label.
+pub fn assemble(source: &str) -> Result<Program, AssembleError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::with_capacity(source.len() / 8);
+    for (index, line) in source.lines().enumerate() {
+        let lno = index + 1;
+        let mut code = line.split(';').next().unwrap_or_default().trim();
+        if let Some(label_end) = code.find(':') {
+            let label = code[..label_end].trim();
+            if label.is_empty() || !matches!(parse_operand(lno, label), Ok(Operand::Label(_))) {
+                return Err(AssembleError::InvalidLabel {
+                    line: lno,
+                    label: label.into(),
+                });
+            }
+            if labels.insert(label, statements.len()).is_some() {
+                return Err(AssembleError::DuplicateLabel {
+                    line: lno,
+                    label: label.into(),
+                });
+            }
+            code = code[label_end + 1..].trim();
+        }
+        if code.is_empty() {
             continue;
         }
-        let mut parts = line.split(char::is_whitespace);
-        let opcode = parts
-            .next()
-            .unwrap_or_else(|| panic!("line {}: no opcode in {:?}", lno, line));
-        let operation = OperationCode::from_str(opcode).unwrap_or_else(|err| {
-            panic!(
-                "line {}: unrecognized opcode {:?}; reason: {:?}",
-                lno, opcode, err
-            )
-        });
-        let arg = parts
-            .next()
-            .unwrap_or_else(|| panic!("line {}: no argument in {:?}", lno, line));
-        let argument = Data::from_str(arg).unwrap_or_else(|err| {
-            panic!(
-                "line {}: argument is not an integer {:?}; reason: {:?}",
-                lno, arg, err
-            )
-        });
-        instructions.push(Instruction {
+        let mut parts = code.split_whitespace();
+        let mnemonic = parts.next().unwrap_or_default();
+        let operands = parts
+            .map(|operand| parse_operand(lno, operand))
+            .collect::<Result<Vec<_>, _>>()?;
+        statements.push((lno, mnemonic, operands));
+    }
+
+    let mut instructions = Vec::with_capacity(statements.len());
+    for (address, (line, mnemonic, operands)) in statements.into_iter().enumerate() {
+        let argument = |operand: Operand<'_>| match operand {
+            Operand::Value(value) => Ok(value),
+            Operand::Label(label) => labels
+                .get(label)
+                .map(|target| *target as Data - address as Data)
+                .ok_or_else(|| AssembleError::UndefinedLabel {
+                    line,
+                    label: label.into(),
+                }),
+            Operand::Register(_) => Err(AssembleError::InvalidArgument {
+                line,
+                argument: operand.to_string(),
+            }),
+        };
+        let register = |operand: Operand<'_>| match operand {
+            Operand::Register(register) => Ok(register),
+            Operand::Value(value) => Err(AssembleError::InvalidRegister {
+                line,
+                name: value.to_string(),
+            }),
+            Operand::Label(name) => Err(AssembleError::InvalidRegister {
+                line,
+                name: name.into(),
+            }),
+        };
+        let instruction = |operation, argument| Instruction {
             operation,
             argument,
-        })
+        };
+        let instruction = match (mnemonic, operands.as_slice()) {
+            ("acc", [value]) => instruction(OperationCode::Acc, argument(*value)?),
+            ("jmp", [offset]) => instruction(OperationCode::Jmp, argument(*offset)?),
+            ("nop", [value]) => instruction(OperationCode::Nop, argument(*value)?),
+            ("set", [r, Operand::Register(s)]) => {
+                instruction(OperationCode::SetR(register(*r)?, *s), 0)
+            },
+            ("set", [r, value]) => {
+                instruction(OperationCode::SetI(register(*r)?), argument(*value)?)
+            },
+            ("add", [r, Operand::Register(s)]) => {
+                instruction(OperationCode::AddR(register(*r)?, *s), 0)
+            },
+            ("add", [r, value]) => {
+                instruction(OperationCode::AddI(register(*r)?), argument(*value)?)
+            },
+            ("mul", [r, Operand::Register(s)]) => {
+                instruction(OperationCode::MulR(register(*r)?, *s), 0)
+            },
+            ("mul", [r, value]) => {
+                instruction(OperationCode::MulI(register(*r)?), argument(*value)?)
+            },
+            ("jz", [r, offset]) => {
+                instruction(OperationCode::Jz(register(*r)?), argument(*offset)?)
+            },
+            ("jnz", [r, offset]) => {
+                instruction(OperationCode::Jnz(register(*r)?), argument(*offset)?)
+            },
+            ("out", [r]) => instruction(OperationCode::Out(register(*r)?), 0),
+            ("acc" | "jmp" | "nop" | "set" | "add" | "mul" | "jz" | "jnz" | "out", _) => {
+                let arity = match mnemonic {
+                    "acc" | "jmp" | "nop" | "out" => 1,
+                    _ => 2,
+                };
+                return Err(match operands.get(arity) {
+                    Some(operand) => AssembleError::UnexpectedOperand {
+                        line,
+                        operand: operand.to_string(),
+                    },
+                    None => AssembleError::MissingOperand { line },
+                });
+            },
+            _ => {
+                return Err(AssembleError::InvalidOperationCode {
+                    line,
+                    opcode: mnemonic.into(),
+                })
+            },
+        };
+        instructions.push(instruction);
     }
 
-    Program::new(instructions)
+    Ok(Program::new(instructions))
+}
+
+/// The maximum number of instructions executed to solve the puzzle.
+const MAX_STEPS: usize = 10_000_000;
+
+#[aoc_generator(day8)]
+pub fn parse_program(input: &str) -> Program {
+    assemble(input).unwrap_or_else(|err| panic!("can not assemble program: {:?}", err))
 }
 
 #[aoc(day8, part1)]
 pub fn accumulator_value_before_second_run(program: &Program) -> Data {
     let vm = VirtualMachine::new(program.clone());
-    let mut registers = Registers::default();
 
-    let mut visited = HashSet::new();
-    loop {
-        if !visited.insert(registers.pc) {
-            break;
-        }
-        match vm.step(&mut registers) {
-            Continuation::Continue => {}
-            Continuation::Halt => break,
-        }
-    }
-    registers.acc
+    vm.trace(Registers::default(), MAX_STEPS).last.acc
 }
 
 #[aoc(day8, part2)]
 pub fn accumulator_value_after_fixing_the_endless_loop(program: &Program) -> Data {
+    let repairs = ControlFlowGraph::analyze(program)
+        .repairs()
+        .expect("can only repair programs without conditional jumps");
+    let patch = match repairs.as_slice() {
+        [patch] => *patch,
+        _ => panic!("no unique repair found: {:?}", repairs),
+    };
     let mut vm = VirtualMachine::new(program.clone());
-    let mut patchable_instructions = program
-        .instructions()
-        .enumerate()
-        .filter_map(|(pc, inst)| match inst.operation {
-            OperationCode::Jmp => Some((pc, OperationCode::Nop)),
-            _ => None,
-        })
-        .collect::<Vec<_>>();
-    while let Some(patched_instruction) = patchable_instructions.pop() {
-        vm.patch(patched_instruction.0, patched_instruction.1);
-        let mut registers = Registers::default();
-        let mut visited = HashSet::new();
-        let continuation = loop {
-            if !visited.insert(registers.pc) {
-                break Continuation::Continue;
-            }
-            match vm.step(&mut registers) {
-                Continuation::Continue => {}
-                Continuation::Halt => break Continuation::Halt,
-            }
-        };
-        match continuation {
-            Continuation::Continue => {}
-            Continuation::Halt => return registers.acc,
-        }
-        vm.patch(patched_instruction.0, OperationCode::Jmp)
-    }
-    panic!("that is not an answer!");
+    vm.patch(patch.address, patch.operation);
+
+    let trace = vm.trace(Registers::default(), MAX_STEPS);
+    debug_assert_eq!(trace.exit, Exit::Halted);
+    trace.last.acc
 }
 
 #[cfg(test)]
@@ -61,6 +61,181 @@ fn parse_program_example() {
     )
 }
 
+const COUNTDOWN: &str = "\
+    set a 3         ; counter
+loop:
+    out a
+    add acc a
+    add a -1
+    jnz a loop
+    mul acc 2
+";
+
+#[test]
+fn assemble_program_with_labels() {
+    let program = assemble(COUNTDOWN).unwrap();
+
+    assert_eq!(
+        program,
+        Program::new(vec![
+            Instruction {
+                operation: OperationCode::SetI(Register::A),
+                argument: 3
+            },
+            Instruction {
+                operation: OperationCode::Out(Register::A),
+                argument: 0
+            },
+            Instruction {
+                operation: OperationCode::AddR(Register::Acc, Register::A),
+                argument: 0
+            },
+            Instruction {
+                operation: OperationCode::AddI(Register::A),
+                argument: -1
+            },
+            Instruction {
+                operation: OperationCode::Jnz(Register::A),
+                argument: -3
+            },
+            Instruction {
+                operation: OperationCode::MulI(Register::Acc),
+                argument: 2
+            },
+        ])
+    );
+}
+
+#[test]
+fn assemble_program_with_undefined_label() {
+    let result = assemble("jmp end\nacc +1\n");
+
+    assert_eq!(
+        result,
+        Err(AssembleError::UndefinedLabel {
+            line: 1,
+            label: "end".into()
+        })
+    );
+}
+
+#[test]
+fn assemble_program_with_label_named_like_a_register() {
+    let result = assemble("a: acc +1\njmp a\n");
+
+    assert_eq!(
+        result,
+        Err(AssembleError::InvalidLabel {
+            line: 1,
+            label: "a".into()
+        })
+    );
+    assert_eq!(
+        assemble(": acc +1\n"),
+        Err(AssembleError::InvalidLabel {
+            line: 1,
+            label: "".into()
+        })
+    );
+}
+
+#[test]
+fn assemble_program_with_invalid_operands() {
+    assert_eq!(
+        assemble("jmp a\n"),
+        Err(AssembleError::InvalidArgument {
+            line: 1,
+            argument: "a".into()
+        })
+    );
+    assert_eq!(
+        assemble("out b c\n"),
+        Err(AssembleError::UnexpectedOperand {
+            line: 1,
+            operand: "c".into()
+        })
+    );
+}
+
+#[test]
+fn assemble_program_with_missing_operand() {
+    let result = assemble("acc +1\njz a\n");
+
+    assert_eq!(result, Err(AssembleError::MissingOperand { line: 2 }));
+}
+
+#[test]
+fn run_program_with_conditional_jump_and_output() {
+    let vm = VirtualMachine::new(assemble(COUNTDOWN).unwrap());
+
+    let run = vm.run(Registers::default(), 100);
+
+    assert_eq!(run.exit, Exit::Halted);
+    assert_eq!(run.output, vec![3, 2, 1]);
+    assert_eq!(run.last.acc, 12);
+    assert_eq!(run.states.len(), 14);
+}
+
+#[test]
+fn trace_program_with_conditional_jump_until_it_halts() {
+    let vm = VirtualMachine::new(assemble(COUNTDOWN).unwrap());
+
+    let trace = vm.trace(Registers::default(), 100);
+
+    assert_eq!(trace.exit, Exit::Halted);
+    assert_eq!(trace.output, vec![3, 2, 1]);
+    assert_eq!(trace.last.acc, 12);
+}
+
+#[test]
+fn trace_program_with_conditional_jump_until_registers_repeat() {
+    let vm = VirtualMachine::new(assemble("set a 2\nloop: mul a -1\njnz a loop\n").unwrap());
+
+    let trace = vm.trace(Registers::default(), 100);
+
+    assert_eq!(
+        trace.exit,
+        Exit::Revisited {
+            address: 1,
+            first_step: 1
+        }
+    );
+    assert_eq!(trace.states.len(), 5);
+}
+
+#[test]
+fn trace_program_with_non_repeating_counter_loop_until_step_limit() {
+    let vm = VirtualMachine::new(assemble("loop: add a 1\njnz a loop\n").unwrap());
+
+    let trace = vm.trace(Registers::default(), 1000);
+
+    assert_eq!(trace.exit, Exit::StepLimit);
+    assert_eq!(trace.states.len(), 1000);
+    assert_eq!(trace.last[Register::A], 500);
+}
+
+#[test]
+fn run_program_until_step_limit() {
+    let vm = VirtualMachine::new(assemble("loop: add a 1\njmp loop\n").unwrap());
+
+    let run = vm.run(Registers::default(), 10);
+
+    assert_eq!(run.exit, Exit::StepLimit);
+    assert_eq!(run.last[Register::A], 5);
+}
+
+#[test]
+fn run_program_with_overflowing_arithmetic() {
+    let vm =
+        VirtualMachine::new(assemble("set a 1\nloop: mul a 2\nadd acc a\njmp loop\n").unwrap());
+
+    let run = vm.run(Registers::default(), 1 + 3 * 64);
+
+    assert_eq!(run.exit, Exit::StepLimit);
+    assert_eq!(run.last[Register::A], 0);
+    assert_eq!(run.last.acc, -2);
+}
+
 #[test]
 fn accumulator_value_before_second_run_of_example_program() {
     let program = parse_program(EXAMPLE);
@@ -79,6 +254,80 @@ fn accumulator_value_before_second_run_of_program_in_puzzle_input() {
     assert_eq!(acc, 1723);
 }
 
+#[test]
+fn trace_example_program_until_first_revisit() {
+    let program = parse_program(EXAMPLE);
+    let vm = VirtualMachine::new(program);
+
+    let trace = vm.trace(Registers::default(), 100);
+
+    assert_eq!(
+        trace.states.iter().map(|reg| reg.pc).collect::<Vec<_>>(),
+        vec![0, 1, 2, 6, 7, 3, 4]
+    );
+    assert_eq!(
+        trace.last,
+        Registers {
+            pc: 1,
+            acc: 5,
+            ..Registers::default()
+        }
+    );
+    assert_eq!(
+        trace.exit,
+        Exit::Revisited {
+            address: 1,
+            first_step: 1
+        }
+    );
+}
+
+#[test]
+fn terminating_instructions_of_example_program() {
+    let program = parse_program(EXAMPLE);
+    let graph = ControlFlowGraph::analyze(&program);
+
+    assert_eq!(
+        (0..program.len())
+            .filter(|address| graph.terminates_from(*address))
+            .collect::<Vec<_>>(),
+        vec![8]
+    );
+    assert_eq!(graph.successors(2), &[Some(6)]);
+    assert_eq!(graph.successors(8), &[None]);
+}
+
+#[test]
+fn repairs_of_example_program() {
+    let program = parse_program(EXAMPLE);
+
+    let repairs = ControlFlowGraph::analyze(&program).repairs();
+
+    assert_eq!(
+        repairs,
+        Some(vec![Patch {
+            address: 7,
+            operation: OperationCode::Nop
+        }])
+    );
+}
+
+#[test]
+fn repairs_of_program_with_conditional_jumps_are_not_supported() {
+    let program = assemble(COUNTDOWN).unwrap();
+
+    assert_eq!(ControlFlowGraph::analyze(&program).repairs(), None);
+}
+
+#[test]
+fn accumulator_value_after_fixing_the_endless_loop_in_example_program() {
+    let program = parse_program(EXAMPLE);
+
+    let acc = accumulator_value_after_fixing_the_endless_loop(&program);
+
+    assert_eq!(acc, 8);
+}
+
 #[test]
 fn accumulator_value_after_fixing_the_endless_loop_in_puzzle_input() {
     let program = parse_program(INPUT);
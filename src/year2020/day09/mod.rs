@@ -115,80 +115,142 @@
 //!
 //! [Advent of Code 2020 - Day 9](https://adventofcode.com/2020/day/9)
 
+use hashbrown::HashMap;
+use std::collections::VecDeque;
 use std::str::FromStr;
 
+pub type Number = i128;
+
+pub const DEFAULT_PREAMBLE_LEN: usize = 25;
+
 #[aoc_generator(day9)]
 pub fn parse_xmas_code(input: &str) -> XmasCode {
     let numbers = input
         .lines()
         .enumerate()
         .map(|line| {
-            i64::from_str(line.1).unwrap_or_else(|err| {
+            Number::from_str(line.1).unwrap_or_else(|err| {
                 panic!("line {}: {:?} no a valid number: {:?}", line.0, line.1, err)
             })
         })
         .collect();
-    XmasCode { numbers }
+    XmasCode {
+        numbers,
+        preamble_len: DEFAULT_PREAMBLE_LEN,
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct XmasCode {
-    numbers: Vec<i64>,
+    numbers: Vec<Number>,
+    preamble_len: usize,
 }
 
-fn is_valid_number(index: usize, preamble_len: usize, numbers: &[i64]) -> bool {
-    assert!(
-        index >= preamble_len,
-        "index must be greater than or equal the length of the preamble"
-    );
+impl XmasCode {
+    pub fn with_preamble_len(mut self, preamble_len: usize) -> Self {
+        self.preamble_len = preamble_len;
+        self
+    }
 
-    let number = numbers[index];
-    numbers
-        .iter()
-        .enumerate()
-        .skip(index - preamble_len)
-        .take(preamble_len)
-        .any(|(i, n1)| {
-            let needed = number - n1;
-            numbers
-                .iter()
-                .skip(i + 1)
-                .take(preamble_len - 1)
-                .any(|n2| *n2 == needed)
-        })
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
+
+    pub fn preamble_len(&self) -> usize {
+        self.preamble_len
+    }
+}
+
+/// Validates a stream of numbers against the preceding window of numbers.
+///
+/// Keeps the sums of all pairs of different numbers in the window as a
+/// multiset. Checking whether a number is the sum of two numbers in the window
+/// is a single lookup. Moving the window on adds and removes the sums of the
+/// entering and the leaving number with each other number in the window.
+#[derive(Debug, Clone)]
+pub struct XmasValidator {
+    preamble_len: usize,
+    window: VecDeque<Number>,
+    pair_sums: HashMap<Number, usize>,
+}
+
+impl XmasValidator {
+    pub fn new(preamble_len: usize) -> Self {
+        Self {
+            preamble_len,
+            window: VecDeque::with_capacity(preamble_len + 1),
+            pair_sums: HashMap::with_capacity(preamble_len * preamble_len),
+        }
+    }
+
+    /// Feeds the next number into the validator and returns whether it is
+    /// valid. Numbers of the preamble are always valid.
+    pub fn push(&mut self, number: Number) -> bool {
+        let valid = self.window.len() < self.preamble_len || self.pair_sums.contains_key(&number);
+
+        if self.window.len() == self.preamble_len {
+            if let Some(leaving) = self.window.pop_front() {
+                for other in self.window.iter().filter(|other| **other != leaving) {
+                    let sum = leaving + *other;
+                    if let Some(count) = self.pair_sums.get_mut(&sum) {
+                        *count -= 1;
+                        if *count == 0 {
+                            self.pair_sums.remove(&sum);
+                        }
+                    }
+                }
+            }
+        }
+        if self.preamble_len > 0 {
+            // the two numbers of a pair must have different values
+            for other in self.window.iter().filter(|other| **other != number) {
+                *self.pair_sums.entry(number + *other).or_insert(0) += 1;
+            }
+            self.window.push_back(number);
+        }
+
+        valid
+    }
 }
 
-fn find_first_invalid_number(preamble_len: usize, numbers: &[i64]) -> Option<i64> {
+fn find_first_invalid_number(preamble_len: usize, numbers: &[Number]) -> Option<Number> {
+    let mut validator = XmasValidator::new(preamble_len);
     numbers
         .iter()
-        .enumerate()
-        .skip(preamble_len)
-        .find(|(i, _)| !is_valid_number(*i, preamble_len, numbers))
-        .map(|(_, n)| *n)
+        .copied()
+        .find(|number| !validator.push(*number))
 }
 
 #[aoc(day9, part1)]
-pub fn first_invalid_number(xmas_code: &XmasCode) -> i64 {
-    find_first_invalid_number(25, &xmas_code.numbers).expect("no invalid number found")
+pub fn first_invalid_number(xmas_code: &XmasCode) -> Number {
+    find_first_invalid_number(xmas_code.preamble_len, &xmas_code.numbers)
+        .expect("no invalid number found")
 }
 
-fn find_sum_of_a_set_of_numbers(num: i64, numbers: &[i64]) -> &[i64] {
-    for i in 0..numbers.len() - 1 {
-        let mut sum = numbers[i];
-        for j in i + 1..numbers.len() {
-            sum += numbers[j];
-            if sum == num {
-                return &numbers[i..j + 1];
-            }
+/// Finds a contiguous set of at least two numbers that sum up to `num`.
+///
+/// Uses a sliding window over the numbers, so all numbers must be
+/// non-negative.
+fn find_sum_of_a_set_of_numbers(num: Number, numbers: &[Number]) -> &[Number] {
+    let mut start = 0;
+    let mut sum = 0;
+    for end in 0..numbers.len() {
+        sum += numbers[end];
+        while sum > num && start < end {
+            sum -= numbers[start];
+            start += 1;
+        }
+        if sum == num && start < end {
+            return &numbers[start..=end];
         }
     }
-    static EMPTY: [i64; 0] = [];
-    &EMPTY
+    &[]
 }
 
 #[aoc(day9, part2)]
-pub fn encryption_weakness(xmas_code: &XmasCode) -> i64 {
-    let num_set = find_sum_of_a_set_of_numbers(542529149, &xmas_code.numbers);
+pub fn encryption_weakness(xmas_code: &XmasCode) -> Number {
+    let invalid_number = first_invalid_number(xmas_code);
+    let num_set = find_sum_of_a_set_of_numbers(invalid_number, &xmas_code.numbers);
     let largest_num = num_set.iter().max().unwrap();
     let smallest_num = num_set.iter().min().unwrap();
     smallest_num + largest_num
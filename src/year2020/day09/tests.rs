@@ -72,6 +72,61 @@ fn find_sum_of_a_set_of_numbers_in_example() {
     assert_eq!(num_set, &vec![15, 25, 47, 40][..]);
 }
 
+#[test]
+fn first_invalid_number_with_configured_preamble_len_in_example() {
+    let xmas_code = parse_xmas_code(EXAMPLE).with_preamble_len(5);
+
+    let first_invalid = first_invalid_number(&xmas_code);
+
+    assert_eq!(first_invalid, 127);
+}
+
+#[test]
+fn encryption_weakness_in_example() {
+    let xmas_code = parse_xmas_code(EXAMPLE).with_preamble_len(5);
+
+    let weakness = encryption_weakness(&xmas_code);
+
+    assert_eq!(weakness, 62);
+}
+
+#[test]
+fn pair_of_equal_numbers_in_window_is_not_valid() {
+    let mut validator = XmasValidator::new(3);
+
+    assert!(validator.push(4));
+    assert!(validator.push(7));
+    assert!(validator.push(4));
+    assert!(!validator.push(8));
+    assert!(validator.push(11));
+    assert!(!validator.push(22));
+    assert!(validator.push(19));
+}
+
+#[test]
+fn find_first_invalid_number_exceeding_i64() {
+    let numbers = [
+        Number::from(i64::MAX),
+        1,
+        Number::from(i64::MAX) + 1,
+        Number::from(i64::MAX) + 2,
+        3,
+    ];
+
+    let first_invalid = find_first_invalid_number(2, &numbers);
+
+    assert_eq!(first_invalid, Some(3));
+}
+
+#[test]
+fn find_sum_of_a_set_of_numbers_not_present() {
+    let numbers = [1, 2, 30, 4];
+
+    let num_set = find_sum_of_a_set_of_numbers(2, &numbers);
+
+    assert!(num_set.is_empty());
+}
+
 #[test]
 fn encryption_weakness_in_puzzle_input() {
     let xmas_code = parse_xmas_code(INPUT);
@@ -192,9 +192,12 @@
 //!
 //! [Advent of Code 2020 - Day 10](https://adventofcode.com/2020/day/10)
 
-use std::collections::HashSet;
 use std::str::FromStr;
 
+/// The maximum difference in joltage between two connected adapters as given
+/// by the puzzle.
+pub const MAX_JOLTAGE_STEP: u32 = 3;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Adapter {
     pub rating: u32,
@@ -206,7 +209,11 @@ impl Adapter {
     }
 
     pub fn can_connect_to_source(&self, source: &Adapter) -> bool {
-        self.rating > source.rating && self.rating - source.rating <= 3
+        self.can_connect_to_source_with_step(source, MAX_JOLTAGE_STEP)
+    }
+
+    pub fn can_connect_to_source_with_step(&self, source: &Adapter, max_step: u32) -> bool {
+        self.rating > source.rating && self.rating - source.rating <= max_step
     }
 }
 
@@ -230,95 +237,72 @@ pub fn parse_adapter_joltages(input: &str) -> Vec<Adapter> {
 }
 
 fn build_chain_of_adapters(all_adapters: &[Adapter]) -> Vec<Adapter> {
+    build_chain_of_adapters_with_step(all_adapters, MAX_JOLTAGE_STEP)
+}
+
+/// Builds the sorted chain from the charging outlet to the device, whose
+/// built-in adapter is rated `max_step` higher than the highest adapter.
+pub fn build_chain_of_adapters_with_step(all_adapters: &[Adapter], max_step: u32) -> Vec<Adapter> {
     let mut all_adapters = all_adapters.to_vec();
     all_adapters.insert(0, SEAT_CHARGING_OUTLET);
     all_adapters.sort_by_key(|adapter| adapter.rating);
     all_adapters.push(Adapter::new(
-        all_adapters.last().map(|a| a.rating).unwrap_or(0) + 3,
+        all_adapters.last().map(|a| a.rating).unwrap_or(0) + max_step,
     ));
     all_adapters
 }
 
+/// Counts how often each difference occurs between consecutive adapters of
+/// the chain. The count of difference `d` is found at index `d`.
+pub fn difference_histogram(adapter_chain: &[Adapter]) -> Vec<usize> {
+    let mut histogram = Vec::new();
+    for (adapter1, adapter2) in adapter_chain.iter().zip(adapter_chain.iter().skip(1)) {
+        let diff = (adapter2.rating - adapter1.rating) as usize;
+        if diff >= histogram.len() {
+            histogram.resize(diff + 1, 0);
+        }
+        histogram[diff] += 1;
+    }
+    histogram
+}
+
 #[aoc(day10, part1)]
 pub fn joltage_differences_in_adapter_chain(all_adapters: &[Adapter]) -> usize {
     let adapter_chain = build_chain_of_adapters(all_adapters);
-    let (diff_one_count, diff_three_count) = adapter_chain
-        .iter()
-        .zip(adapter_chain.iter().skip(1))
-        .map(|(adapter1, adapter2)| adapter2.rating - adapter1.rating)
-        .fold((0, 0), |(ones, threes), diff| match diff {
-            1 => (ones + 1, threes),
-            3 => (ones, threes + 1),
-            _ => (ones, threes),
-        });
-    diff_one_count * diff_three_count
-}
-
-fn indexed_differences(adapter_chain: &[Adapter]) -> Vec<(usize, u32)> {
-    adapter_chain
-        .iter()
-        .zip(adapter_chain.iter().enumerate().skip(1))
-        .map(|(a1, (index, a2))| (index, a2.rating - a1.rating))
-        .collect()
+    let histogram = difference_histogram(&adapter_chain);
+    let count = |diff: usize| histogram.get(diff).copied().unwrap_or(0);
+    count(1) * count(3)
 }
 
-fn mark_removable(indexed_differences: &[(usize, u32)]) -> Vec<(usize, bool)> {
-    [(0, false)]
-        .iter()
-        .copied()
-        .chain(
-            indexed_differences
+/// Counts the distinct arrangements of adapters that connect the first adapter
+/// of the sorted chain to the last one.
+///
+/// The number of ways to reach an adapter is the sum of the ways to reach
+/// each lower rated adapter it can be connected to.
+pub fn count_arrangements(adapter_chain: &[Adapter], max_step: u32) -> u128 {
+    let mut ways: Vec<u128> = Vec::with_capacity(adapter_chain.len());
+    for (index, adapter) in adapter_chain.iter().enumerate() {
+        let count = if index == 0 {
+            1
+        } else {
+            adapter_chain[..index]
                 .iter()
-                .zip(indexed_differences.iter().skip(1))
-                .map(|((i1, diff1), (_, diff2))| (*i1, *diff1 == 1 && *diff2 == 1)),
-        )
-        .chain([(indexed_differences.len(), false)].iter().copied())
-        .collect()
-}
-
-fn split_into_sub_chains(adapter_chain: &[Adapter]) -> Vec<Vec<Adapter>> {
-    let mut sub_chains = Vec::new();
-    let mut prev_index = 0;
-    for index in mark_removable(&indexed_differences(adapter_chain))
-        .iter()
-        .filter_map(|(index, removable)| if !removable { Some(*index) } else { None })
-    {
-        if index - prev_index > 1 {
-            let sub_chain = adapter_chain[prev_index..=index].iter().cloned().collect();
-            sub_chains.push(sub_chain);
-        }
-        prev_index = index;
-    }
-    sub_chains
-}
-
-fn count_adapter_combinations(adapter_chain: &[Adapter]) -> usize {
-    let mut count = 1;
-    for sub_chain in split_into_sub_chains(adapter_chain) {
-        let mut found_chains = HashSet::new();
-        let mut open_sub_chains = vec![sub_chain.to_vec()];
-        while let Some(sub_chain) = open_sub_chains.pop() {
-            if !found_chains.contains(&sub_chain) {
-                for i in 1..sub_chain.len() - 1 {
-                    if sub_chain[i + 1].rating - sub_chain[i - 1].rating <= 3 {
-                        let mut new_sub_chain = sub_chain.clone();
-                        new_sub_chain.remove(i);
-                        open_sub_chains.push(new_sub_chain);
-                    }
-                }
-                found_chains.insert(sub_chain);
-            }
-        }
-        count *= found_chains.len();
-        found_chains.clear()
+                .zip(&ways)
+                .rev()
+                .skip_while(|(source, _)| source.rating == adapter.rating)
+                .take_while(|(source, _)| adapter.can_connect_to_source_with_step(source, max_step))
+                .map(|(_, count)| count)
+                .sum()
+        };
+        ways.push(count);
     }
-    count
+    ways.last().copied().unwrap_or(0)
 }
 
 #[aoc(day10, part2)]
-pub fn number_of_possible_adapter_arrangements(all_adapters: &[Adapter]) -> usize {
+pub fn number_of_possible_adapter_arrangements(all_adapters: &[Adapter]) -> u128 {
     let adapter_chain = build_chain_of_adapters(all_adapters);
-    count_adapter_combinations(&adapter_chain)
+    count_arrangements(&adapter_chain, MAX_JOLTAGE_STEP)
 }
 
 #[cfg(test)]
@@ -241,55 +241,64 @@ fn joltage_differences_in_adapter_chain_in_puzzle_input() {
 // }
 
 #[test]
-fn mark_removable_in_example1() {
+fn difference_histogram_in_example1() {
     let all_adapters = parse_adapter_joltages(EXAMPLE1);
     let adapter_chain = build_chain_of_adapters(&all_adapters);
 
-    let removable = mark_removable(&indexed_differences(&adapter_chain));
+    let histogram = difference_histogram(&adapter_chain);
 
-    assert_eq!(
-        removable,
-        vec![
-            (0, false),
-            (1, false),
-            (2, false),
-            (3, true),
-            (4, true),
-            (5, false),
-            (6, false),
-            (7, true),
-            (8, false),
-            (9, false),
-            (10, false),
-            (11, false),
-            (12, false),
-        ]
-    );
+    assert_eq!(histogram, vec![0, 7, 0, 5]);
+}
+
+#[test]
+fn difference_histogram_with_differences_of_2() {
+    let adapter_chain =
+        build_chain_of_adapters(&[Adapter::new(1), Adapter::new(3), Adapter::new(4)]);
+
+    let histogram = difference_histogram(&adapter_chain);
+
+    assert_eq!(histogram, vec![0, 2, 1, 1]);
+}
+
+#[test]
+fn count_arrangements_with_differences_of_2() {
+    let adapter_chain =
+        build_chain_of_adapters(&[Adapter::new(1), Adapter::new(3), Adapter::new(4)]);
+
+    let num_arrangements = count_arrangements(&adapter_chain, 3);
+
+    assert_eq!(num_arrangements, 3);
 }
 
 #[test]
-fn split_into_sub_chains_in_example1() {
+fn count_arrangements_with_max_step_of_4() {
+    let all_adapters = [Adapter::new(2), Adapter::new(4), Adapter::new(6)];
+    let adapter_chain = build_chain_of_adapters_with_step(&all_adapters, 4);
+
+    let num_arrangements = count_arrangements(&adapter_chain, 4);
+
+    assert_eq!(adapter_chain.last(), Some(&Adapter::new(10)));
+    assert_eq!(num_arrangements, 3);
+}
+
+#[test]
+fn count_arrangements_with_max_step_of_1_in_example1() {
     let all_adapters = parse_adapter_joltages(EXAMPLE1);
+    let adapter_chain = build_chain_of_adapters_with_step(&all_adapters, 1);
+
+    let num_arrangements = count_arrangements(&adapter_chain, 1);
+
+    assert_eq!(num_arrangements, 0);
+}
+
+#[test]
+fn count_arrangements_exceeding_u64() {
+    let all_adapters = (1..=100).map(Adapter::new).collect::<Vec<_>>();
     let adapter_chain = build_chain_of_adapters(&all_adapters);
 
-    let sub_chains = split_into_sub_chains(&adapter_chain);
+    let num_arrangements = count_arrangements(&adapter_chain, 3);
 
-    assert_eq!(
-        sub_chains,
-        vec![
-            vec![
-                Adapter { rating: 4 },
-                Adapter { rating: 5 },
-                Adapter { rating: 6 },
-                Adapter { rating: 7 },
-            ],
-            vec![
-                Adapter { rating: 10 },
-                Adapter { rating: 11 },
-                Adapter { rating: 12 },
-            ],
-        ]
-    );
+    assert_eq!(num_arrangements, 180396380815100901214157639);
 }
 
 #[test]
@@ -302,6 +302,47 @@ pub enum Neighbor {
     NorthWest,
 }
 
+impl Neighbor {
+    /// Returns the row and column offset of this neighbor.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Neighbor::North => (-1, 0),
+            Neighbor::NorthEast => (-1, 1),
+            Neighbor::East => (0, 1),
+            Neighbor::SouthEast => (1, 1),
+            Neighbor::South => (1, 0),
+            Neighbor::SouthWest => (1, -1),
+            Neighbor::West => (0, -1),
+            Neighbor::NorthWest => (-1, -1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// A seat sees the eight seats immediately around it.
+    Adjacent,
+    /// A seat sees the first seat in each of the eight directions.
+    LineOfSight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeatingRules {
+    pub visibility: Visibility,
+    /// The number of visible occupied seats that makes people leave a seat.
+    pub tolerance: usize,
+}
+
+pub const RULES_PART1: SeatingRules = SeatingRules {
+    visibility: Visibility::Adjacent,
+    tolerance: 4,
+};
+
+pub const RULES_PART2: SeatingRules = SeatingRules {
+    visibility: Visibility::LineOfSight,
+    tolerance: 5,
+};
+
 #[aoc_generator(day11)]
 pub fn parse_initial_seat_layout(input: &str) -> Vec<Vec<Tile>> {
     let mut rows = Vec::new();
@@ -315,271 +356,235 @@ pub fn parse_initial_seat_layout(input: &str) -> Vec<Vec<Tile>> {
                 if !current_row.is_empty() {
                     rows.push(mem::replace(&mut current_row, Vec::new()));
                 }
-            }
+            },
             _ => panic!("unrecognized tile {:?}", c),
         }
     }
     rows
 }
 
-fn count_tiles(state: Tile, layout: &[Vec<Tile>]) -> usize {
-    let mut count = 0;
-    for row in layout {
-        for tile in row {
-            if *tile == state {
-                count += 1
-            }
-        }
-    }
-    count
+const NO_SEAT: u32 = u32::MAX;
+
+/// The seats of a layout and for each seat the seats visible from it.
+///
+/// Seats are numbered in row major order. The visible seats are stored as one
+/// compact adjacency list, where the seats visible from seat `i` are
+/// `visible[offsets[i]..offsets[i + 1]]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeatGraph {
+    positions: Vec<(usize, usize)>,
+    offsets: Vec<usize>,
+    visible: Vec<u32>,
 }
 
-fn next_generation_part1(prev_layout: &[Vec<Tile>], next_layout: &mut [Vec<Tile>]) {
-    for row in 0..prev_layout.len() {
-        for col in 0..prev_layout[row].len() {
-            let prev_state = prev_layout[row][col];
-            let new_state = match prev_state {
-                Tile::Floor => prev_state,
-                Tile::EmptySeat => {
-                    if 0 == count_neighbors_part1(Tile::OccupiedSeat, row, col, prev_layout) {
-                        Tile::OccupiedSeat
-                    } else {
-                        prev_state
-                    }
+impl SeatGraph {
+    pub fn new(layout: &[Vec<Tile>], visibility: Visibility) -> Self {
+        let num_rows = layout.len();
+        let num_cols = layout.iter().map(Vec::len).max().unwrap_or(0);
+
+        let mut seat_at = vec![NO_SEAT; num_rows * num_cols];
+        let mut positions = Vec::new();
+        for (row, tiles) in layout.iter().enumerate() {
+            for (col, tile) in tiles.iter().enumerate() {
+                if *tile != Tile::Floor {
+                    seat_at[row * num_cols + col] = positions.len() as u32;
+                    positions.push((row, col));
                 }
-                Tile::OccupiedSeat => {
-                    if 4 <= count_neighbors_part1(Tile::OccupiedSeat, row, col, prev_layout) {
-                        Tile::EmptySeat
+            }
+        }
+
+        let mut slots = vec![NO_SEAT; positions.len() * NEIGHBORS.len()];
+        let mut nearest = vec![NO_SEAT; num_rows * num_cols];
+        for (slot, neighbor) in NEIGHBORS.iter().enumerate() {
+            let (d_row, d_col) = neighbor.offset();
+            // visit the cells in the neighbor direction first, so that the
+            // nearest seat of the neighbor cell is known when it is needed
+            for row_index in 0..num_rows {
+                let row = if d_row > 0 {
+                    num_rows - 1 - row_index
+                } else {
+                    row_index
+                };
+                for col_index in 0..num_cols {
+                    let col = if d_col > 0 {
+                        num_cols - 1 - col_index
                     } else {
-                        prev_state
+                        col_index
+                    };
+                    let cell = row * num_cols + col;
+                    let neighbor_cell = match (
+                        row.checked_add_signed(d_row).filter(|r| *r < num_rows),
+                        col.checked_add_signed(d_col).filter(|c| *c < num_cols),
+                    ) {
+                        (Some(r), Some(c)) => r * num_cols + c,
+                        _ => {
+                            nearest[cell] = NO_SEAT;
+                            continue;
+                        },
+                    };
+                    let neighbor_seat = seat_at[neighbor_cell];
+                    nearest[cell] = match visibility {
+                        Visibility::LineOfSight if neighbor_seat == NO_SEAT => {
+                            nearest[neighbor_cell]
+                        },
+                        _ => neighbor_seat,
+                    };
+                    let seat = seat_at[cell];
+                    if seat != NO_SEAT {
+                        slots[seat as usize * NEIGHBORS.len() + slot] = nearest[cell];
                     }
                 }
-            };
-            next_layout[row][col] = new_state;
+            }
+        }
+
+        let mut offsets = Vec::with_capacity(positions.len() + 1);
+        let mut visible = Vec::with_capacity(slots.len());
+        offsets.push(0);
+        for seat_slots in slots.chunks(NEIGHBORS.len()) {
+            visible.extend(seat_slots.iter().filter(|seat| **seat != NO_SEAT));
+            offsets.push(visible.len());
+        }
+
+        Self {
+            positions,
+            offsets,
+            visible,
         }
     }
+
+    pub fn num_seats(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns the row and column of the given seat.
+    pub fn position(&self, seat: usize) -> (usize, usize) {
+        self.positions[seat]
+    }
+
+    pub fn visible_seats(&self, seat: usize) -> &[u32] {
+        &self.visible[self.offsets[seat]..self.offsets[seat + 1]]
+    }
 }
 
-fn count_neighbors_part1(state: Tile, row: usize, col: usize, layout: &[Vec<Tile>]) -> usize {
-    let mut count = 0;
-    let num_rows = layout.len();
-    let num_cols = layout[row].len();
-    for neighbor in &NEIGHBORS {
-        match neighbor {
-            Neighbor::North => {
-                if row > 0 && layout[row - 1][col] == state {
-                    count += 1;
-                }
-            }
-            Neighbor::NorthEast => {
-                if row > 0 && col < num_cols - 1 && layout[row - 1][col + 1] == state {
-                    count += 1;
-                }
-            }
-            Neighbor::East => {
-                if col < num_cols - 1 && layout[row][col + 1] == state {
-                    count += 1;
-                }
-            }
-            Neighbor::SouthEast => {
-                if row < num_rows - 1 && col < num_cols - 1 && layout[row + 1][col + 1] == state {
-                    count += 1;
-                }
-            }
-            Neighbor::South => {
-                if row < num_rows - 1 && layout[row + 1][col] == state {
-                    count += 1;
-                }
-            }
-            Neighbor::SouthWest => {
-                if row < num_rows - 1 && col > 0 && layout[row + 1][col - 1] == state {
-                    count += 1;
-                }
-            }
-            Neighbor::West => {
-                if col > 0 && layout[row][col - 1] == state {
-                    count += 1;
-                }
+fn is_set(bits: &[u64], index: usize) -> bool {
+    bits[index / 64] & (1 << (index % 64)) != 0
+}
+
+/// Simulates the seating area generation by generation.
+///
+/// The occupied seats are held in two bitsets, one for the current and one for
+/// the next generation, which are swapped after each generation.
+#[derive(Debug, Clone)]
+pub struct SeatingSimulation<'a> {
+    graph: &'a SeatGraph,
+    tolerance: usize,
+    current: Vec<u64>,
+    next: Vec<u64>,
+    generation: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stability {
+    /// The number of generations that changed the seating area.
+    pub generations: usize,
+    pub occupied_seats: usize,
+}
+
+impl<'a> SeatingSimulation<'a> {
+    pub fn new(graph: &'a SeatGraph, layout: &[Vec<Tile>], tolerance: usize) -> Self {
+        let num_words = graph.num_seats().div_ceil(64);
+        let mut current = vec![0; num_words];
+        for (seat, (row, col)) in graph.positions.iter().enumerate() {
+            if layout[*row][*col] == Tile::OccupiedSeat {
+                current[seat / 64] |= 1 << (seat % 64);
             }
-            Neighbor::NorthWest => {
-                if row > 0 && col > 0 && layout[row - 1][col - 1] == state {
-                    count += 1;
+        }
+        Self {
+            graph,
+            tolerance,
+            current,
+            next: vec![0; num_words],
+            generation: 0,
+        }
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn occupied_seats(&self) -> usize {
+        self.current
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Computes the next generation. Returns whether any seat changed.
+    pub fn step(&mut self) -> bool {
+        let num_seats = self.graph.num_seats();
+        let mut changed = false;
+        for (index, word) in self.next.iter_mut().enumerate() {
+            let first_seat = index * 64;
+            let mut bits = 0;
+            for seat in first_seat..num_seats.min(first_seat + 64) {
+                let occupied_neighbors = self
+                    .graph
+                    .visible_seats(seat)
+                    .iter()
+                    .filter(|other| is_set(&self.current, **other as usize))
+                    .count();
+                let occupied = if is_set(&self.current, seat) {
+                    occupied_neighbors < self.tolerance
+                } else {
+                    occupied_neighbors == 0
+                };
+                if occupied {
+                    bits |= 1 << (seat - first_seat);
                 }
             }
+            changed |= bits != self.current[index];
+            *word = bits;
+        }
+        mem::swap(&mut self.current, &mut self.next);
+        if changed {
+            self.generation += 1;
         }
+        changed
     }
-    count
-}
 
-fn next_generation_part2(prev_layout: &[Vec<Tile>], next_layout: &mut [Vec<Tile>]) {
-    for row in 0..prev_layout.len() {
-        for col in 0..prev_layout[row].len() {
-            let prev_state = prev_layout[row][col];
-            let new_state = match prev_state {
-                Tile::Floor => prev_state,
-                Tile::EmptySeat => {
-                    if 0 == count_neighbors_part2(Tile::OccupiedSeat, row, col, prev_layout) {
-                        Tile::OccupiedSeat
-                    } else {
-                        prev_state
-                    }
-                }
-                Tile::OccupiedSeat => {
-                    if 5 <= count_neighbors_part2(Tile::OccupiedSeat, row, col, prev_layout) {
-                        Tile::EmptySeat
-                    } else {
-                        prev_state
-                    }
-                }
-            };
-            next_layout[row][col] = new_state;
+    pub fn run_until_stable(&mut self) -> Stability {
+        while self.step() {}
+        Stability {
+            generations: self.generation,
+            occupied_seats: self.occupied_seats(),
         }
     }
-}
 
-fn count_neighbors_part2(state: Tile, row: usize, col: usize, layout: &[Vec<Tile>]) -> usize {
-    let mut count = 0;
-    let num_rows = layout.len();
-    let num_cols = layout[row].len();
-    for neighbor in &NEIGHBORS {
-        match neighbor {
-            Neighbor::North => {
-                for ri in (0..row).rev() {
-                    match layout[ri][col] {
-                        s if s == state => {
-                            count += 1;
-                            break;
-                        }
-                        Tile::Floor => {}
-                        _ => break,
-                    }
-                }
-            }
-            Neighbor::NorthEast => {
-                if col < num_cols - 1 {
-                    for (ri, ci) in (0..row).rev().zip(col + 1..num_cols) {
-                        match layout[ri][ci] {
-                            s if s == state => {
-                                count += 1;
-                                break;
-                            }
-                            Tile::Floor => {}
-                            _ => break,
-                        }
-                    }
-                }
-            }
-            Neighbor::East => {
-                if col < num_cols - 1 {
-                    for ci in col + 1..num_cols {
-                        match layout[row][ci] {
-                            s if s == state => {
-                                count += 1;
-                                break;
-                            }
-                            Tile::Floor => {}
-                            _ => break,
-                        }
-                    }
-                }
-            }
-            Neighbor::SouthEast => {
-                if row < num_rows - 1 && col < num_cols - 1 {
-                    for (ri, ci) in (row + 1..num_rows).zip(col + 1..num_cols) {
-                        match layout[ri][ci] {
-                            s if s == state => {
-                                count += 1;
-                                break;
-                            }
-                            Tile::Floor => {}
-                            _ => break,
-                        }
-                    }
-                }
-            }
-            Neighbor::South => {
-                if row < num_rows - 1 {
-                    for ri in row + 1..num_rows {
-                        match layout[ri][col] {
-                            s if s == state => {
-                                count += 1;
-                                break;
-                            }
-                            Tile::Floor => {}
-                            _ => break,
-                        }
-                    }
-                }
-            }
-            Neighbor::SouthWest => {
-                if row < num_rows - 1 {
-                    for (ri, ci) in (row + 1..num_rows).zip((0..col).rev()) {
-                        match layout[ri][ci] {
-                            s if s == state => {
-                                count += 1;
-                                break;
-                            }
-                            Tile::Floor => {}
-                            _ => break,
-                        }
-                    }
-                }
-            }
-            Neighbor::West => {
-                for ci in (0..col).rev() {
-                    match layout[row][ci] {
-                        s if s == state => {
-                            count += 1;
-                            break;
-                        }
-                        Tile::Floor => {}
-                        _ => break,
-                    }
-                }
-            }
-            Neighbor::NorthWest => {
-                for (ri, ci) in (0..row).rev().zip((0..col).rev()) {
-                    match layout[ri][ci] {
-                        s if s == state => {
-                            count += 1;
-                            break;
-                        }
-                        Tile::Floor => {}
-                        _ => break,
-                    }
-                }
-            }
+    /// Writes the state of the seats into the given layout.
+    pub fn write_layout(&self, layout: &mut [Vec<Tile>]) {
+        for (seat, (row, col)) in self.graph.positions.iter().enumerate() {
+            layout[*row][*col] = if is_set(&self.current, seat) {
+                Tile::OccupiedSeat
+            } else {
+                Tile::EmptySeat
+            };
         }
     }
-    count
+}
+
+pub fn simulate_until_stable(initial_layout: &[Vec<Tile>], rules: SeatingRules) -> Stability {
+    let graph = SeatGraph::new(initial_layout, rules.visibility);
+    SeatingSimulation::new(&graph, initial_layout, rules.tolerance).run_until_stable()
 }
 
 #[aoc(day11, part1)]
 pub fn number_of_occupied_seats_part1(initial_layout: &[Vec<Tile>]) -> usize {
-    let mut prev_layout = initial_layout.to_vec();
-    let mut next_layout = initial_layout.to_vec();
-    loop {
-        next_generation_part1(&prev_layout, &mut next_layout);
-        if next_layout == prev_layout {
-            break;
-        }
-        mem::swap(&mut prev_layout, &mut next_layout);
-    }
-    count_tiles(Tile::OccupiedSeat, &next_layout)
+    simulate_until_stable(initial_layout, RULES_PART1).occupied_seats
 }
 
 #[aoc(day11, part2)]
 pub fn number_of_occupied_seats_part2(initial_layout: &[Vec<Tile>]) -> usize {
-    let mut prev_layout = initial_layout.to_vec();
-    let mut next_layout = initial_layout.to_vec();
-    loop {
-        next_generation_part2(&prev_layout, &mut next_layout);
-        if next_layout == prev_layout {
-            break;
-        }
-        mem::swap(&mut prev_layout, &mut next_layout);
-    }
-    count_tiles(Tile::OccupiedSeat, &next_layout)
+    simulate_until_stable(initial_layout, RULES_PART2).occupied_seats
 }
 
 #[cfg(test)]
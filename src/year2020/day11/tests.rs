@@ -15,6 +15,46 @@ L.LLLLLL.L
 L.LLLLL.LL
 ";
 
+const EXAMPLE2: &str = "\
+.......#.
+...#.....
+.#.......
+.........
+..#L....#
+....#....
+.........
+#........
+...#.....
+";
+
+const EXAMPLE3: &str = "\
+.##.##.
+#.#.#.#
+##...##
+...L...
+##...##
+#.#.#.#
+.##.##.
+";
+
+fn next_generation_part1(prev_layout: &[Vec<Tile>], next_layout: &mut [Vec<Tile>]) {
+    let graph = SeatGraph::new(prev_layout, RULES_PART1.visibility);
+    let mut simulation = SeatingSimulation::new(&graph, prev_layout, RULES_PART1.tolerance);
+    simulation.step();
+    simulation.write_layout(next_layout);
+}
+
+fn visible_positions(graph: &SeatGraph, position: (usize, usize)) -> Vec<(usize, usize)> {
+    let seat = (0..graph.num_seats())
+        .find(|seat| graph.position(*seat) == position)
+        .unwrap();
+    graph
+        .visible_seats(seat)
+        .iter()
+        .map(|other| graph.position(*other as usize))
+        .collect()
+}
+
 #[test]
 fn parse_initial_seat_layout_in_example1() {
     let seat_layout = parse_initial_seat_layout(EXAMPLE1);
@@ -67,6 +107,65 @@ fn one_generation_after_initial_layout_in_example1() {
     )
 }
 
+#[test]
+fn line_of_sight_sees_eight_seats_in_example2() {
+    let layout = parse_initial_seat_layout(EXAMPLE2);
+    let graph = SeatGraph::new(&layout, Visibility::LineOfSight);
+
+    assert_eq!(
+        visible_positions(&graph, (4, 3)),
+        vec![
+            (1, 3),
+            (0, 7),
+            (4, 8),
+            (5, 4),
+            (8, 3),
+            (7, 0),
+            (4, 2),
+            (2, 1)
+        ]
+    );
+}
+
+#[test]
+fn line_of_sight_sees_no_seats_in_example3() {
+    let layout = parse_initial_seat_layout(EXAMPLE3);
+    let graph = SeatGraph::new(&layout, Visibility::LineOfSight);
+
+    assert_eq!(visible_positions(&graph, (3, 3)), vec![]);
+}
+
+#[test]
+fn adjacent_seats_at_corner_in_example1() {
+    let layout = parse_initial_seat_layout(EXAMPLE1);
+    let graph = SeatGraph::new(&layout, Visibility::Adjacent);
+
+    assert_eq!(visible_positions(&graph, (0, 0)), vec![(1, 1), (1, 0)]);
+}
+
+#[test]
+fn generations_until_stable_in_example1() {
+    let seat_layout = parse_initial_seat_layout(EXAMPLE1);
+
+    let part1 = simulate_until_stable(&seat_layout, RULES_PART1);
+    let part2 = simulate_until_stable(&seat_layout, RULES_PART2);
+
+    assert_eq!(
+        part1,
+        Stability {
+            generations: 5,
+            occupied_seats: 37
+        }
+    );
+    assert_eq!(
+        part2,
+        Stability {
+            generations: 6,
+            occupied_seats: 26
+        }
+    );
+}
+
 #[test]
 fn number_of_occupied_seats_for_part1_in_example1() {
     let seat_layout = parse_initial_seat_layout(EXAMPLE1);
@@ -103,6 +103,8 @@
 //!
 //! [Advent of Code 2020 - Day 12](https://adventofcode.com/2020/day/12)
 
+use std::convert::TryFrom;
+use std::iter::FromIterator;
 use std::ops::{Add, AddAssign};
 use std::str::FromStr;
 
@@ -119,11 +121,48 @@ pub enum Navigate {
     RotateRight(Angle),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Angle {
-    D90,
-    D180,
-    D270,
+/// An angle in whole degrees, normalized to the range 0 to 359.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Angle(i32);
+
+impl Angle {
+    pub const D0: Angle = Angle(0);
+    pub const D45: Angle = Angle(45);
+    pub const D90: Angle = Angle(90);
+    pub const D135: Angle = Angle(135);
+    pub const D180: Angle = Angle(180);
+    pub const D225: Angle = Angle(225);
+    pub const D270: Angle = Angle(270);
+    pub const D315: Angle = Angle(315);
+
+    pub fn from_degrees(degrees: i32) -> Self {
+        Angle(degrees.rem_euclid(360))
+    }
+
+    pub fn degrees(self) -> i32 {
+        self.0
+    }
+
+    /// Returns the number of 45° steps if the angle is a multiple of 45°.
+    pub fn eighths(self) -> Option<u8> {
+        if self.0 % 45 == 0 {
+            Some((self.0 / 45) as u8)
+        } else {
+            None
+        }
+    }
+
+    pub fn negate(self) -> Self {
+        Angle::from_degrees(-self.0)
+    }
+}
+
+impl Add for Angle {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Angle::from_degrees(self.0 + other.0)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -153,58 +192,35 @@ impl Position {
     }
 }
 
-impl Add<Move> for Position {
-    type Output = Self;
-
-    fn add(
+impl Position {
+    /// Moves the position along one of the four cardinal directions. Returns
+    /// an error on diagonal directions, because the resulting position is not
+    /// on the integer grid. Use `ExactPosition` for diagonal moves.
+    pub fn try_move(
         self,
         Move {
             distance,
             direction,
         }: Move,
-    ) -> Self::Output {
+    ) -> Result<Self, NavigationError> {
         match direction {
-            Direction::North => Self {
+            Direction::North => Ok(Self {
                 north: self.north + distance,
                 east: self.east,
-            },
-            Direction::East => Self {
+            }),
+            Direction::East => Ok(Self {
                 north: self.north,
                 east: self.east + distance,
-            },
-            Direction::South => Self {
+            }),
+            Direction::South => Ok(Self {
                 north: self.north - distance,
                 east: self.east,
-            },
-            Direction::West => Self {
+            }),
+            Direction::West => Ok(Self {
                 north: self.north,
                 east: self.east - distance,
-            },
-        }
-    }
-}
-
-impl AddAssign<Move> for Position {
-    fn add_assign(
-        &mut self,
-        Move {
-            distance,
-            direction,
-        }: Move,
-    ) {
-        match direction {
-            Direction::North => {
-                self.north += distance;
-            }
-            Direction::East => {
-                self.east += distance;
-            }
-            Direction::South => {
-                self.north -= distance;
-            }
-            Direction::West => {
-                self.east -= distance;
-            }
+            }),
+            _ => Err(NavigationError::OffGrid(direction)),
         }
     }
 }
@@ -212,60 +228,47 @@ impl AddAssign<Move> for Position {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     North,
+    NorthEast,
     East,
+    SouthEast,
     South,
+    SouthWest,
     West,
+    NorthWest,
 }
 
+const DIRECTIONS: [Direction; 8] = [
+    Direction::North,
+    Direction::NorthEast,
+    Direction::East,
+    Direction::SouthEast,
+    Direction::South,
+    Direction::SouthWest,
+    Direction::West,
+    Direction::NorthWest,
+];
+
 impl Direction {
-    pub fn rotate_cw(self, angle: Angle) -> Self {
-        match self {
-            Direction::North => match angle {
-                Angle::D90 => Direction::East,
-                Angle::D180 => Direction::South,
-                Angle::D270 => Direction::West,
-            },
-            Direction::East => match angle {
-                Angle::D90 => Direction::South,
-                Angle::D180 => Direction::West,
-                Angle::D270 => Direction::North,
-            },
-            Direction::South => match angle {
-                Angle::D90 => Direction::West,
-                Angle::D180 => Direction::North,
-                Angle::D270 => Direction::East,
-            },
-            Direction::West => match angle {
-                Angle::D90 => Direction::North,
-                Angle::D180 => Direction::East,
-                Angle::D270 => Direction::South,
-            },
-        }
+    /// Returns the heading of this direction, measured clockwise from north.
+    pub fn heading(self) -> Angle {
+        Angle::from_degrees(DIRECTIONS.iter().position(|dir| *dir == self).unwrap_or(0) as i32 * 45)
     }
 
-    pub fn rotate_ccw(self, angle: Angle) -> Self {
-        match self {
-            Direction::North => match angle {
-                Angle::D90 => Direction::West,
-                Angle::D180 => Direction::South,
-                Angle::D270 => Direction::East,
-            },
-            Direction::East => match angle {
-                Angle::D90 => Direction::North,
-                Angle::D180 => Direction::West,
-                Angle::D270 => Direction::South,
-            },
-            Direction::South => match angle {
-                Angle::D90 => Direction::East,
-                Angle::D180 => Direction::North,
-                Angle::D270 => Direction::West,
-            },
-            Direction::West => match angle {
-                Angle::D90 => Direction::South,
-                Angle::D180 => Direction::East,
-                Angle::D270 => Direction::North,
-            },
-        }
+    /// Returns the direction of the given heading if it is a multiple of 45°.
+    pub fn from_heading(heading: Angle) -> Option<Self> {
+        heading
+            .eighths()
+            .map(|eighths| DIRECTIONS[eighths as usize])
+    }
+
+    /// Returns `None` if the angle is not a multiple of 45°.
+    pub fn rotate_cw(self, angle: Angle) -> Option<Self> {
+        Direction::from_heading(self.heading() + angle)
+    }
+
+    /// Returns `None` if the angle is not a multiple of 45°.
+    pub fn rotate_ccw(self, angle: Angle) -> Option<Self> {
+        self.rotate_cw(angle.negate())
     }
 }
 
@@ -287,64 +290,262 @@ impl Default for Ship {
 }
 
 impl Ship {
-    pub fn move_(&mut self, miles: Mile, direction: Direction) {
-        self.position += Move::from((miles, direction))
+    pub fn move_(&mut self, miles: Mile, direction: Direction) -> Result<(), NavigationError> {
+        self.position = self.position.try_move(Move::from((miles, direction)))?;
+        Ok(())
     }
 
-    pub fn turn_left(&mut self, angle: Angle) {
-        self.facing = self.facing.rotate_ccw(angle);
+    pub fn move_waypoint(
+        &mut self,
+        miles: Mile,
+        direction: Direction,
+    ) -> Result<(), NavigationError> {
+        self.waypoint = self.waypoint.try_move(Move::from((miles, direction)))?;
+        Ok(())
+    }
+
+    pub fn turn_left(&mut self, angle: Angle) -> Result<(), NavigationError> {
+        self.facing = self
+            .facing
+            .rotate_ccw(angle)
+            .ok_or(NavigationError::UnsupportedRotation(angle))?;
+        Ok(())
     }
 
-    pub fn turn_right(&mut self, angle: Angle) {
-        self.facing = self.facing.rotate_cw(angle);
+    pub fn turn_right(&mut self, angle: Angle) -> Result<(), NavigationError> {
+        self.facing = self
+            .facing
+            .rotate_cw(angle)
+            .ok_or(NavigationError::UnsupportedRotation(angle))?;
+        Ok(())
     }
 
-    pub fn rotate_waypoint_cw(&mut self, angle: Angle) {
+    /// Returns an error if the angle is not a multiple of 90°.
+    pub fn rotate_waypoint_cw(&mut self, angle: Angle) -> Result<(), NavigationError> {
         let north = self.waypoint.north;
         let east = self.waypoint.east;
         match angle {
+            Angle::D0 => {},
             Angle::D90 => {
                 self.waypoint.north = -east;
                 self.waypoint.east = north;
-            }
+            },
             Angle::D180 => {
                 self.waypoint.north = -north;
                 self.waypoint.east = -east;
-            }
+            },
             Angle::D270 => {
                 self.waypoint.north = east;
                 self.waypoint.east = -north;
-            }
+            },
+            _ => return Err(NavigationError::UnsupportedWaypointRotation(angle)),
         }
+        Ok(())
     }
 
-    pub fn rotate_waypoint_ccw(&mut self, angle: Angle) {
-        let north = self.waypoint.north;
-        let east = self.waypoint.east;
-        match angle {
-            Angle::D90 => {
-                self.waypoint.north = east;
-                self.waypoint.east = -north;
-            }
-            Angle::D180 => {
-                self.waypoint.north = -north;
-                self.waypoint.east = -east;
-            }
-            Angle::D270 => {
-                self.waypoint.north = -east;
-                self.waypoint.east = north;
-            }
-        }
+    /// Returns an error if the angle is not a multiple of 90°.
+    pub fn rotate_waypoint_ccw(&mut self, angle: Angle) -> Result<(), NavigationError> {
+        self.rotate_waypoint_cw(angle.negate())
+            .map_err(|_| NavigationError::UnsupportedWaypointRotation(angle))
     }
 }
 
 pub trait AutoPilot {
-    fn apply(instruction: Navigate, ship: &mut Ship);
+    fn apply(instruction: Navigate, ship: &mut Ship) -> Result<(), NavigationError>;
 
-    fn execute(instructions: &[Navigate], ship: &mut Ship) {
+    fn execute(instructions: &[Navigate], ship: &mut Ship) -> Result<(), NavigationError> {
         instructions
-            .into_iter()
-            .for_each(|inst| Self::apply(*inst, ship))
+            .iter()
+            .try_for_each(|inst| Self::apply(*inst, ship))
+    }
+
+    /// Executes the instructions like `execute` and records the state of the
+    /// ship before the first and after each instruction.
+    fn execute_recording(
+        instructions: &[Navigate],
+        ship: &mut Ship,
+    ) -> Result<Route, NavigationError> {
+        let mut states = Vec::with_capacity(instructions.len() + 1);
+        states.push(*ship);
+        for inst in instructions {
+            Self::apply(*inst, ship)?;
+            states.push(*ship);
+        }
+        Ok(Route { states })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub states: Vec<Ship>,
+}
+
+impl Route {
+    pub fn ship_positions(&self) -> Vec<Position> {
+        self.states.iter().map(|ship| ship.position).collect()
+    }
+
+    pub fn waypoint_positions(&self) -> Vec<Position> {
+        self.states.iter().map(|ship| ship.waypoint).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    min: Position,
+    max: Position,
+}
+
+impl Bounds {
+    fn of<'a>(tracks: impl IntoIterator<Item = &'a [Position]>) -> Self {
+        let mut positions = tracks.into_iter().flatten();
+        let first = positions
+            .next()
+            .copied()
+            .unwrap_or(Position { north: 0, east: 0 });
+        positions.fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |bounds, pos| Bounds {
+                min: Position {
+                    north: bounds.min.north.min(pos.north),
+                    east: bounds.min.east.min(pos.east),
+                },
+                max: Position {
+                    north: bounds.max.north.max(pos.north),
+                    east: bounds.max.east.max(pos.east),
+                },
+            },
+        )
+    }
+
+    /// The extents of long routes may not fit into a `Mile`.
+    fn width(self) -> i64 {
+        i64::from(self.max.east) - i64::from(self.min.east)
+    }
+
+    fn height(self) -> i64 {
+        i64::from(self.max.north) - i64::from(self.min.north)
+    }
+}
+
+const TRACK_COLORS: [&str; 4] = ["steelblue", "orangered", "seagreen", "darkorchid"];
+
+/// Renders the tracks as polylines in an SVG image. North points up.
+pub fn render_svg(tracks: &[Vec<Position>]) -> String {
+    let bounds = Bounds::of(tracks.iter().map(Vec::as_slice));
+    let margin = (bounds.width().max(bounds.height()) / 20).max(1);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        i64::from(bounds.min.east) - margin,
+        -i64::from(bounds.max.north) - margin,
+        bounds.width() + 2 * margin,
+        bounds.height() + 2 * margin
+    );
+    for (index, track) in tracks.iter().enumerate() {
+        let points = track
+            .iter()
+            .map(|pos| format!("{},{}", pos.east, -i64::from(pos.north)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        svg.push_str(&format!(
+            "  <polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" \
+             vector-effect=\"non-scaling-stroke\" points=\"{}\"/>\n",
+            TRACK_COLORS[index % TRACK_COLORS.len()],
+            points
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders the track scaled into a map of `width` x `height` characters.
+///
+/// The track is drawn with `#`, the start is marked with `S` and the end with
+/// `E`. North points up.
+pub fn render_ascii(track: &[Position], width: usize, height: usize) -> Vec<String> {
+    if width == 0 || height == 0 {
+        return vec![String::new(); height];
+    }
+    let mut map = vec![vec!['.'; width]; height];
+    let bounds = Bounds::of([track]);
+    let scale = |pos: &Position| {
+        let col = (i64::from(pos.east) - i64::from(bounds.min.east)) as i128 * (width as i128 - 1)
+            / i128::from(bounds.width().max(1));
+        let row = (i64::from(bounds.max.north) - i64::from(pos.north)) as i128
+            * (height as i128 - 1)
+            / i128::from(bounds.height().max(1));
+        (row, col)
+    };
+    for segment in track.windows(2) {
+        let (row0, col0) = scale(&segment[0]);
+        let (row1, col1) = scale(&segment[1]);
+        let steps = (row1 - row0).abs().max((col1 - col0).abs()).max(1);
+        for step in 0..=steps {
+            let row = row0 + (row1 - row0) * step / steps;
+            let col = col0 + (col1 - col0) * step / steps;
+            map[row as usize][col as usize] = '#';
+        }
+    }
+    if let (Some(start), Some(end)) = (track.first(), track.last()) {
+        let (row, col) = scale(end);
+        map[row as usize][col as usize] = 'E';
+        let (row, col) = scale(start);
+        map[row as usize][col as usize] = 'S';
+    }
+    map.into_iter().map(String::from_iter).collect()
+}
+
+/// The routes of the ship when the instructions are interpreted as moving the
+/// ship (part 1) and as moving the waypoint (part 2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteComparison {
+    pub ship_moves: Route,
+    pub waypoint_moves: Route,
+}
+
+impl RouteComparison {
+    pub fn new(instructions: &[Navigate]) -> Result<Self, NavigationError> {
+        Ok(Self {
+            ship_moves: AutoPilot1::execute_recording(instructions, &mut Ship::default())?,
+            waypoint_moves: AutoPilot2::execute_recording(instructions, &mut Ship::default())?,
+        })
+    }
+
+    /// Returns the number of instructions after which the ship positions of
+    /// both routes differ for the first time.
+    pub fn divergence(&self) -> Option<usize> {
+        self.ship_moves
+            .states
+            .iter()
+            .zip(&self.waypoint_moves.states)
+            .position(|(ship1, ship2)| ship1.position != ship2.position)
+    }
+
+    pub fn to_svg(&self) -> String {
+        render_svg(&[
+            self.ship_moves.ship_positions(),
+            self.waypoint_moves.ship_positions(),
+        ])
+    }
+
+    /// Renders both routes as ASCII maps side by side. Each map is scaled on
+    /// its own.
+    pub fn to_ascii(&self, width: usize, height: usize) -> String {
+        let left = render_ascii(&self.ship_moves.ship_positions(), width, height);
+        let right = render_ascii(&self.waypoint_moves.ship_positions(), width, height);
+        let mut ascii = format!(
+            "{:<width$} | {}\n",
+            "ship moves",
+            "waypoint moves",
+            width = width
+        );
+        for (left_line, right_line) in left.iter().zip(&right) {
+            ascii.push_str(&format!("{} | {}\n", left_line, right_line));
+        }
+        ascii
     }
 }
 
@@ -371,28 +572,8 @@ pub fn parse_navigation_instructions(input: &str) -> Vec<Navigate> {
             'S' => Navigate::South(amount),
             'E' => Navigate::East(amount),
             'W' => Navigate::West(amount),
-            'L' => {
-                if let Some(angle) = parse_angle(amount) {
-                    Navigate::RotateLeft(angle)
-                } else {
-                    panic!(
-                        "line {}: invalid amount for turn left: {:?}",
-                        lno + 1,
-                        amount
-                    );
-                }
-            }
-            'R' => {
-                if let Some(angle) = parse_angle(amount) {
-                    Navigate::RotateRight(angle)
-                } else {
-                    panic!(
-                        "line {}: invalid amount for turn left: {:?}",
-                        lno + 1,
-                        amount
-                    );
-                }
-            }
+            'L' => Navigate::RotateLeft(Angle::from_degrees(amount)),
+            'R' => Navigate::RotateRight(Angle::from_degrees(amount)),
             'F' => Navigate::Forward(amount),
             _ => {
                 panic!(
@@ -400,7 +581,7 @@ pub fn parse_navigation_instructions(input: &str) -> Vec<Navigate> {
                     lno + 1,
                     line
                 );
-            }
+            },
         };
 
         instructions.push(instruction);
@@ -408,20 +589,11 @@ pub fn parse_navigation_instructions(input: &str) -> Vec<Navigate> {
     instructions
 }
 
-fn parse_angle(amount: i32) -> Option<Angle> {
-    match amount {
-        90 => Some(Angle::D90),
-        180 => Some(Angle::D180),
-        270 => Some(Angle::D270),
-        _ => None,
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 pub struct AutoPilot1;
 
 impl AutoPilot for AutoPilot1 {
-    fn apply(instruction: Navigate, ship: &mut Ship) {
+    fn apply(instruction: Navigate, ship: &mut Ship) -> Result<(), NavigationError> {
         match instruction {
             Navigate::Forward(miles) => ship.move_(miles, ship.facing),
             Navigate::North(miles) => ship.move_(miles, Direction::North),
@@ -434,40 +606,273 @@ impl AutoPilot for AutoPilot1 {
     }
 }
 
+/// Returns `None` if the ship does not end up on the integer grid. Turns that
+/// are not right angles are left to the `Navigator`.
 #[aoc(day12, part1)]
-pub fn part1_distance_to_final_position(instructions: &[Navigate]) -> Mile {
+pub fn part1_distance_to_final_position(instructions: &[Navigate]) -> Option<Mile> {
     let mut ship = Ship::default();
     let start_position = ship.position;
-    AutoPilot1::execute(instructions, &mut ship);
-    ship.position.manhattan_distance(start_position)
+    let final_position = match AutoPilot1::execute(instructions, &mut ship) {
+        Ok(()) => ship.position,
+        Err(_) => navigate_ship_moves(instructions).exact_position()?,
+    };
+    Some(final_position.manhattan_distance(start_position))
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct AutoPilot2;
 
 impl AutoPilot for AutoPilot2 {
-    fn apply(instruction: Navigate, ship: &mut Ship) {
+    fn apply(instruction: Navigate, ship: &mut Ship) -> Result<(), NavigationError> {
         match instruction {
             Navigate::Forward(times) => {
-                ship.move_(times * ship.waypoint.north, Direction::North);
-                ship.move_(times * ship.waypoint.east, Direction::East);
-            }
-            Navigate::North(miles) => ship.waypoint += Move::from((miles, Direction::North)),
-            Navigate::East(miles) => ship.waypoint += Move::from((miles, Direction::East)),
-            Navigate::South(miles) => ship.waypoint += Move::from((miles, Direction::South)),
-            Navigate::West(miles) => ship.waypoint += Move::from((miles, Direction::West)),
+                ship.move_(times * ship.waypoint.north, Direction::North)?;
+                ship.move_(times * ship.waypoint.east, Direction::East)
+            },
+            Navigate::North(miles) => ship.move_waypoint(miles, Direction::North),
+            Navigate::East(miles) => ship.move_waypoint(miles, Direction::East),
+            Navigate::South(miles) => ship.move_waypoint(miles, Direction::South),
+            Navigate::West(miles) => ship.move_waypoint(miles, Direction::West),
             Navigate::RotateLeft(angle) => ship.rotate_waypoint_ccw(angle),
             Navigate::RotateRight(angle) => ship.rotate_waypoint_cw(angle),
         }
     }
 }
 
+/// Returns `None` if the ship does not end up on the integer grid or the
+/// waypoint is rotated by an angle that is not a multiple of 45°. Turns that
+/// are not right angles are left to the `Navigator`.
 #[aoc(day12, part2)]
-pub fn part2_distance_to_final_position(instructions: &[Navigate]) -> Mile {
+pub fn part2_distance_to_final_position(instructions: &[Navigate]) -> Option<Mile> {
     let mut ship = Ship::default();
     let start_position = ship.position;
-    AutoPilot2::execute(instructions, &mut ship);
-    ship.position.manhattan_distance(start_position)
+    let final_position = match AutoPilot2::execute(instructions, &mut ship) {
+        Ok(()) => ship.position,
+        Err(_) => navigate_waypoint_moves(instructions)
+            .ok()?
+            .exact_position()?,
+    };
+    Some(final_position.manhattan_distance(start_position))
+}
+
+/// A position stored exactly as an integer combination of the unit vectors of
+/// the compass directions.
+///
+/// The coefficients `[a, b, c, d]` stand for `a + b·ω + c·ω² + d·ω³`, where
+/// `ω` is the unit vector pointing north-east, i.e. east rotated by 45°
+/// counterclockwise. As `ω⁴ = -1`, moves along any of the eight compass
+/// directions and rotations by multiples of 45° stay in integer arithmetic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ExactPosition(pub [i64; 4]);
+
+impl ExactPosition {
+    pub fn unit(direction: Direction) -> Self {
+        let clockwise_from_north = direction.heading().eighths().unwrap_or(0) as usize;
+        let counterclockwise_from_east = (10 - clockwise_from_north) % 8;
+        let mut coefficients = [0; 4];
+        if counterclockwise_from_east < 4 {
+            coefficients[counterclockwise_from_east] = 1;
+        } else {
+            coefficients[counterclockwise_from_east - 4] = -1;
+        }
+        Self(coefficients)
+    }
+
+    pub fn scale(self, factor: i64) -> Self {
+        Self(self.0.map(|coefficient| coefficient * factor))
+    }
+
+    fn rotate_eighth_ccw(self) -> Self {
+        let [a, b, c, d] = self.0;
+        Self([-d, a, b, c])
+    }
+
+    /// Rotates the position around the origin. Returns `None` if the angle is
+    /// not a multiple of 45°.
+    pub fn rotate_ccw(self, angle: Angle) -> Option<Self> {
+        let eighths = angle.eighths()?;
+        Some((0..eighths).fold(self, |pos, _| pos.rotate_eighth_ccw()))
+    }
+
+    /// Rotates the position around the origin. Returns `None` if the angle is
+    /// not a multiple of 45°.
+    pub fn rotate_cw(self, angle: Angle) -> Option<Self> {
+        self.rotate_ccw(angle.negate())
+    }
+
+    pub fn north(self) -> f64 {
+        let [_, b, c, d] = self.0;
+        c as f64 + (b + d) as f64 * std::f64::consts::FRAC_1_SQRT_2
+    }
+
+    pub fn east(self) -> f64 {
+        let [a, b, _, d] = self.0;
+        a as f64 + (b - d) as f64 * std::f64::consts::FRAC_1_SQRT_2
+    }
+
+    /// Returns the position on the integer grid if this position is on it.
+    pub fn to_position(self) -> Option<Position> {
+        match self.0 {
+            [east, 0, north, 0] => Some(Position {
+                north: Mile::try_from(north).ok()?,
+                east: Mile::try_from(east).ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl From<Position> for ExactPosition {
+    fn from(position: Position) -> Self {
+        Self([i64::from(position.east), 0, i64::from(position.north), 0])
+    }
+}
+
+impl Add for ExactPosition {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        let [a, b, c, d] = self.0;
+        let [e, f, g, h] = other.0;
+        Self([a + e, b + f, c + g, d + h])
+    }
+}
+
+impl AddAssign for ExactPosition {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationError {
+    /// The waypoint can only be rotated by multiples of 45°, or of 90° on the
+    /// integer grid.
+    UnsupportedWaypointRotation(Angle),
+    /// The ship can only turn by multiples of 45°.
+    UnsupportedRotation(Angle),
+    /// The move along the diagonal direction leaves the integer grid.
+    OffGrid(Direction),
+}
+
+/// A ship that can take any heading in whole degrees and rotate its waypoint
+/// by multiples of 45°.
+///
+/// Moves along headings that are a multiple of 45° are exact. Moves along any
+/// other heading are summed up separately as `deviation` in floating point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Navigator {
+    pub position: ExactPosition,
+    /// The north and east part of the moves along inexact headings
+    pub deviation: (f64, f64),
+    /// The heading of the ship, measured clockwise from north
+    pub heading: Angle,
+    pub waypoint: ExactPosition,
+}
+
+impl Default for Navigator {
+    fn default() -> Self {
+        let ship = Ship::default();
+        Self {
+            position: ExactPosition::from(ship.position),
+            deviation: (0., 0.),
+            heading: ship.facing.heading(),
+            waypoint: ExactPosition::from(ship.waypoint),
+        }
+    }
+}
+
+fn compass_move(instruction: Navigate) -> Option<ExactPosition> {
+    let (miles, direction) = match instruction {
+        Navigate::North(miles) => (miles, Direction::North),
+        Navigate::East(miles) => (miles, Direction::East),
+        Navigate::South(miles) => (miles, Direction::South),
+        Navigate::West(miles) => (miles, Direction::West),
+        _ => return None,
+    };
+    Some(ExactPosition::unit(direction).scale(i64::from(miles)))
+}
+
+impl Navigator {
+    /// Applies the instruction according to the rules of part 1, moving the
+    /// ship itself.
+    pub fn apply_ship_moves(&mut self, instruction: Navigate) {
+        match instruction {
+            Navigate::Forward(miles) => match Direction::from_heading(self.heading) {
+                Some(direction) => {
+                    self.position += ExactPosition::unit(direction).scale(i64::from(miles))
+                },
+                None => {
+                    let radians = f64::from(self.heading.degrees()).to_radians();
+                    self.deviation.0 += f64::from(miles) * radians.cos();
+                    self.deviation.1 += f64::from(miles) * radians.sin();
+                },
+            },
+            Navigate::RotateLeft(angle) => self.heading = self.heading + angle.negate(),
+            Navigate::RotateRight(angle) => self.heading = self.heading + angle,
+            _ => self.position += compass_move(instruction).unwrap_or_default(),
+        }
+    }
+
+    /// Applies the instruction according to the rules of part 2, moving the
+    /// waypoint.
+    pub fn apply_waypoint_moves(&mut self, instruction: Navigate) -> Result<(), NavigationError> {
+        match instruction {
+            Navigate::Forward(times) => self.position += self.waypoint.scale(i64::from(times)),
+            Navigate::RotateLeft(angle) => {
+                self.waypoint = self
+                    .waypoint
+                    .rotate_ccw(angle)
+                    .ok_or(NavigationError::UnsupportedWaypointRotation(angle))?
+            },
+            Navigate::RotateRight(angle) => {
+                self.waypoint = self
+                    .waypoint
+                    .rotate_cw(angle)
+                    .ok_or(NavigationError::UnsupportedWaypointRotation(angle))?
+            },
+            _ => self.waypoint += compass_move(instruction).unwrap_or_default(),
+        }
+        Ok(())
+    }
+
+    pub fn north(&self) -> f64 {
+        self.position.north() + self.deviation.0
+    }
+
+    pub fn east(&self) -> f64 {
+        self.position.east() + self.deviation.1
+    }
+
+    /// Returns the position on the integer grid if the ship is exactly on it.
+    pub fn exact_position(&self) -> Option<Position> {
+        if self.deviation == (0., 0.) {
+            self.position.to_position()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the manhattan distance from the origin.
+    pub fn manhattan_distance(&self) -> f64 {
+        self.north().abs() + self.east().abs()
+    }
+}
+
+pub fn navigate_ship_moves(instructions: &[Navigate]) -> Navigator {
+    let mut navigator = Navigator::default();
+    for instruction in instructions {
+        navigator.apply_ship_moves(*instruction);
+    }
+    navigator
+}
+
+pub fn navigate_waypoint_moves(instructions: &[Navigate]) -> Result<Navigator, NavigationError> {
+    let mut navigator = Navigator::default();
+    for instruction in instructions {
+        navigator.apply_waypoint_moves(*instruction)?;
+    }
+    Ok(navigator)
 }
 
 #[cfg(test)]
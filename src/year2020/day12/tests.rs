@@ -52,7 +52,7 @@ fn execute_instructions_according_rules_part1_in_example() {
     let instructions = parse_navigation_instructions(EXAMPLE);
     let mut ship = Ship::default();
 
-    AutoPilot1::execute(&instructions, &mut ship);
+    AutoPilot1::execute(&instructions, &mut ship).unwrap();
 
     assert_eq!(
         ship,
@@ -73,7 +73,7 @@ fn part1_distance_to_final_position_in_example() {
 
     let distance = part1_distance_to_final_position(&instructions);
 
-    assert_eq!(distance, 25);
+    assert_eq!(distance, Some(25));
 }
 
 #[test]
@@ -82,7 +82,7 @@ fn part1_distance_to_final_position_in_puzzle_input() {
 
     let distance = part1_distance_to_final_position(&instructions);
 
-    assert_eq!(distance, 923);
+    assert_eq!(distance, Some(923));
 }
 
 #[test]
@@ -90,7 +90,7 @@ fn execute_instructions_according_rules_part2_in_example() {
     let instructions = parse_navigation_instructions(EXAMPLE);
     let mut ship = Ship::default();
 
-    AutoPilot2::execute(&instructions, &mut ship);
+    AutoPilot2::execute(&instructions, &mut ship).unwrap();
 
     assert_eq!(
         ship,
@@ -114,7 +114,7 @@ fn part2_distance_to_final_position_in_example() {
 
     let distance = part2_distance_to_final_position(&instructions);
 
-    assert_eq!(distance, 286);
+    assert_eq!(distance, Some(286));
 }
 
 #[test]
@@ -123,5 +123,290 @@ fn part2_distance_to_final_position_in_puzzle_input() {
 
     let distance = part2_distance_to_final_position(&instructions);
 
-    assert_eq!(distance, 24769);
+    assert_eq!(distance, Some(24769));
+}
+
+#[test]
+fn record_route_according_rules_part1_in_example() {
+    let instructions = parse_navigation_instructions(EXAMPLE);
+
+    let route = AutoPilot1::execute_recording(&instructions, &mut Ship::default()).unwrap();
+
+    assert_eq!(
+        route.ship_positions(),
+        vec![
+            Position { north: 0, east: 0 },
+            Position { north: 0, east: 10 },
+            Position { north: 3, east: 10 },
+            Position { north: 3, east: 17 },
+            Position { north: 3, east: 17 },
+            Position {
+                north: -8,
+                east: 17
+            },
+        ]
+    );
+}
+
+#[test]
+fn record_route_according_rules_part2_in_example() {
+    let instructions = parse_navigation_instructions(EXAMPLE);
+
+    let route = AutoPilot2::execute_recording(&instructions, &mut Ship::default()).unwrap();
+
+    assert_eq!(
+        route.waypoint_positions(),
+        vec![
+            Position { north: 1, east: 10 },
+            Position { north: 1, east: 10 },
+            Position { north: 4, east: 10 },
+            Position { north: 4, east: 10 },
+            Position {
+                north: -10,
+                east: 4
+            },
+            Position {
+                north: -10,
+                east: 4
+            },
+        ]
+    );
+    assert_eq!(
+        route.states.last().map(|ship| ship.position),
+        Some(Position {
+            north: -72,
+            east: 214
+        })
+    );
+}
+
+#[test]
+fn render_route_as_svg() {
+    let svg = render_svg(&[vec![
+        Position { north: 0, east: 0 },
+        Position { north: 0, east: 10 },
+        Position {
+            north: -20,
+            east: 10,
+        },
+    ]]);
+
+    assert_eq!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -1 12 22\">\n  \
+         <polyline fill=\"none\" stroke=\"steelblue\" stroke-width=\"2\" \
+         vector-effect=\"non-scaling-stroke\" points=\"0,0 10,0 10,20\"/>\n\
+         </svg>\n"
+    );
+}
+
+#[test]
+fn render_route_according_rules_part1_as_ascii_in_example() {
+    let instructions = parse_navigation_instructions(EXAMPLE);
+    let route = AutoPilot1::execute_recording(&instructions, &mut Ship::default()).unwrap();
+
+    let map = render_ascii(&route.ship_positions(), 9, 5);
+
+    assert_eq!(
+        map,
+        vec![
+            "....#####",
+            "S####...#",
+            "........#",
+            "........#",
+            "........E",
+        ]
+    );
+}
+
+#[test]
+fn render_route_spanning_more_than_mile_range() {
+    let track = [
+        Position {
+            north: Mile::MIN,
+            east: Mile::MIN,
+        },
+        Position {
+            north: Mile::MAX,
+            east: Mile::MAX,
+        },
+    ];
+
+    let map = render_ascii(&track, 3, 3);
+    let svg = render_svg(&[track.to_vec()]);
+
+    assert_eq!(map, vec!["..E", ".#.", "S.."]);
+    assert!(svg.contains("viewBox=\"-2362232012 -2362232011 4724464023 4724464023\""));
+}
+
+#[test]
+fn compare_routes_in_example() {
+    let instructions = parse_navigation_instructions(EXAMPLE);
+
+    let comparison = RouteComparison::new(&instructions).unwrap();
+
+    assert_eq!(comparison.divergence(), Some(1));
+    assert_eq!(comparison.to_ascii(4, 2).lines().count(), 3);
+    assert_eq!(comparison.to_svg().matches("<polyline").count(), 2);
+}
+
+#[test]
+fn parse_navigation_instructions_with_arbitrary_angles() {
+    let instructions = parse_navigation_instructions("R45\nL30\nR450\n");
+
+    assert_eq!(
+        instructions,
+        vec![
+            Navigate::RotateRight(Angle::D45),
+            Navigate::RotateLeft(Angle::from_degrees(30)),
+            Navigate::RotateRight(Angle::D90),
+        ]
+    );
+}
+
+#[test]
+fn rotate_direction_by_multiples_of_45_degrees() {
+    assert_eq!(
+        Direction::North.rotate_cw(Angle::D45),
+        Some(Direction::NorthEast)
+    );
+    assert_eq!(
+        Direction::North.rotate_ccw(Angle::D135),
+        Some(Direction::SouthWest)
+    );
+    assert_eq!(
+        Direction::West.rotate_cw(Angle::D270),
+        Some(Direction::South)
+    );
+    assert_eq!(Direction::West.rotate_cw(Angle::from_degrees(30)), None);
+    assert_eq!(Direction::from_heading(Angle::from_degrees(30)), None);
+}
+
+#[test]
+fn navigate_example_with_extended_model() {
+    let instructions = parse_navigation_instructions(EXAMPLE);
+
+    let ship_moves = navigate_ship_moves(&instructions);
+    let waypoint_moves = navigate_waypoint_moves(&instructions).unwrap();
+
+    assert_eq!(
+        ship_moves.exact_position(),
+        Some(Position {
+            north: -8,
+            east: 17
+        })
+    );
+    assert_eq!(
+        waypoint_moves.exact_position(),
+        Some(Position {
+            north: -72,
+            east: 214
+        })
+    );
+}
+
+#[test]
+fn rotate_waypoint_twice_by_45_degrees_is_exact() {
+    let instructions = parse_navigation_instructions("R45\nF1\nR45\nF1\n");
+
+    let navigator = navigate_waypoint_moves(&instructions).unwrap();
+
+    assert_eq!(navigator.exact_position(), None);
+    assert_eq!(
+        navigator.waypoint.to_position(),
+        Some(Position {
+            north: -10,
+            east: 1
+        })
+    );
+    assert!((navigator.north() - (-9. / 2f64.sqrt() - 10.)).abs() < 1e-9);
+    assert!((navigator.east() - (11. / 2f64.sqrt() + 1.)).abs() < 1e-9);
+}
+
+#[test]
+fn rotate_waypoint_by_30_degrees_is_not_supported() {
+    let instructions = parse_navigation_instructions("F10\nL30\nF10\n");
+
+    let result = navigate_waypoint_moves(&instructions);
+
+    assert_eq!(
+        result,
+        Err(NavigationError::UnsupportedWaypointRotation(
+            Angle::from_degrees(30)
+        ))
+    );
+}
+
+#[test]
+fn move_ship_along_arbitrary_heading() {
+    let instructions = parse_navigation_instructions("L30\nF10\nR75\nF4\n");
+
+    let navigator = navigate_ship_moves(&instructions);
+
+    assert_eq!(navigator.heading, Angle::D135);
+    assert_eq!(
+        navigator.position,
+        ExactPosition::unit(Direction::SouthEast).scale(4)
+    );
+    assert!((navigator.deviation.0 - 5.).abs() < 1e-9);
+    assert!((navigator.deviation.1 - 75f64.sqrt()).abs() < 1e-9);
+    assert_eq!(navigator.exact_position(), None);
+}
+
+#[test]
+fn grid_model_reports_unsupported_angles() {
+    let mut ship = Ship::default();
+
+    assert_eq!(
+        ship.turn_left(Angle::from_degrees(30)),
+        Err(NavigationError::UnsupportedRotation(Angle::from_degrees(
+            30
+        )))
+    );
+    assert_eq!(
+        ship.rotate_waypoint_cw(Angle::D45),
+        Err(NavigationError::UnsupportedWaypointRotation(Angle::D45))
+    );
+    assert_eq!(ship.turn_right(Angle::D45), Ok(()));
+    assert_eq!(
+        ship.move_(10, ship.facing),
+        Err(NavigationError::OffGrid(Direction::SouthEast))
+    );
+    assert_eq!(
+        ship,
+        Ship {
+            facing: Direction::SouthEast,
+            ..Ship::default()
+        }
+    );
+}
+
+#[test]
+fn compare_routes_with_unsupported_angles() {
+    let instructions = parse_navigation_instructions("R45\nF10\n");
+
+    assert_eq!(
+        RouteComparison::new(&instructions),
+        Err(NavigationError::OffGrid(Direction::SouthEast))
+    );
+}
+
+#[test]
+fn part1_distance_with_angles_that_are_not_right_angles() {
+    let back_on_grid = parse_navigation_instructions("R45\nR45\nF10\nL30\nR30\nN3\n");
+    let off_grid = parse_navigation_instructions("L30\nF10\n");
+
+    assert_eq!(part1_distance_to_final_position(&back_on_grid), Some(7));
+    assert_eq!(part1_distance_to_final_position(&off_grid), None);
+}
+
+#[test]
+fn part2_distance_with_angles_that_are_not_right_angles() {
+    let back_on_grid = parse_navigation_instructions("R45\nR45\nF10\n");
+    let off_grid = parse_navigation_instructions("R45\nF10\n");
+    let unsupported = parse_navigation_instructions("L30\nF10\n");
+
+    assert_eq!(part2_distance_to_final_position(&back_on_grid), Some(110));
+    assert_eq!(part2_distance_to_final_position(&off_grid), None);
+    assert_eq!(part2_distance_to_final_position(&unsupported), None);
 }
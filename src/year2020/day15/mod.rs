@@ -76,40 +76,21 @@
 //!
 //! [Advent of Code 2020 - Day 15](https://adventofcode.com/2020/day/15)
 
-use fxhash::FxBuildHasher;
-use hashbrown::HashMap;
 use std::str::FromStr;
 
 pub type Number = u32;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct History {
-    turns: [u32; 2],
-}
-
-impl History {
-    pub fn new() -> Self {
-        Self { turns: [0; 2] }
-    }
-
-    pub fn age(&self) -> Option<u32> {
-        if self.turns[1] == 0 {
-            None
-        } else {
-            Some(self.turns[0] - self.turns[1])
-        }
-    }
-
-    pub fn spoken_at_turn(&mut self, turn: u32) {
-        debug_assert!(turn > 0, "turn must be greater 0");
-        self.turns[1] = self.turns[0];
-        self.turns[0] = turn;
-    }
-}
+/// Marks a number that has not been spoken yet. Turns are counted from 1.
+const NEVER: u32 = 0;
 
+/// The memory of the game.
+///
+/// For every number it remembers the turn the number was last spoken, except
+/// for the most recently spoken number, whose previous turn is still needed to
+/// determine the next number. Numbers are used as index into a dense table.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Memory {
-    history: HashMap<Number, History, FxBuildHasher>,
+    last_seen: Vec<u32>,
     last_number: Number,
     last_turn: u32,
 }
@@ -117,19 +98,40 @@ pub struct Memory {
 impl Memory {
     pub fn new() -> Self {
         Self {
-            history: HashMap::with_hasher(FxBuildHasher::default()),
+            last_seen: Vec::new(),
+            last_number: 0,
+            last_turn: 0,
+        }
+    }
+
+    /// Creates a memory sized for a game of the given number of turns.
+    ///
+    /// Each number spoken after the starting numbers is an age and therefore
+    /// lower than the turn count, so the table does not grow during such a
+    /// game unless a starting number is larger than the turn count.
+    pub fn with_capacity(turns: usize) -> Self {
+        Self {
+            last_seen: vec![NEVER; turns],
             last_number: 0,
             last_turn: 0,
         }
     }
 
+    fn remember_last_number(&mut self) -> u32 {
+        let index = self.last_number as usize;
+        if index >= self.last_seen.len() {
+            self.last_seen.resize(index + 1, NEVER);
+        }
+        std::mem::replace(&mut self.last_seen[index], self.last_turn)
+    }
+
     pub fn add_spoken_number(&mut self, turn: u32, number: Number) {
+        debug_assert!(turn > 0, "turn must be greater 0");
+        if self.last_turn != 0 {
+            self.remember_last_number();
+        }
         self.last_turn = turn;
         self.last_number = number;
-        self.history
-            .entry(number)
-            .or_insert_with(History::new)
-            .spoken_at_turn(turn);
     }
 
     pub fn last_spoken_number(&self) -> Number {
@@ -141,16 +143,47 @@ impl Memory {
     }
 }
 
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Iterator for Memory {
     type Item = Number;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let next_number = self.history[&self.last_number].age().unwrap_or(0);
-        self.add_spoken_number(self.last_turn + 1, next_number);
+        let previous_turn = self.remember_last_number();
+        let next_number = if previous_turn == NEVER {
+            0
+        } else {
+            self.last_turn - previous_turn
+        };
+        self.last_turn += 1;
+        self.last_number = next_number;
         Some(next_number)
     }
 }
 
+/// Determines the number spoken at the given turn, counting turns from 1.
+///
+/// Returns `None` for turn 0, for turns the memory can not count to or if
+/// there are no starting numbers.
+pub fn nth(numbers: &[Number], turn: usize) -> Option<Number> {
+    if turn == 0 || numbers.is_empty() || u32::try_from(turn).is_err() {
+        return None;
+    }
+    if turn <= numbers.len() {
+        return Some(numbers[turn - 1]);
+    }
+    let mut memory = Memory::with_capacity(turn);
+    numbers
+        .iter()
+        .enumerate()
+        .for_each(|(index, num)| memory.add_spoken_number(index as u32 + 1, *num));
+    memory.nth(turn - numbers.len() - 1)
+}
+
 #[aoc_generator(day15)]
 pub fn parse_starting_numbers(input: &str) -> Vec<u32> {
     input
@@ -163,7 +196,7 @@ pub fn parse_starting_numbers(input: &str) -> Vec<u32> {
 
 #[aoc(day15, part1)]
 pub fn determine_the_2020th_number_spoken(numbers: &[u32]) -> u32 {
-    let mut memory = Memory::new();
+    let mut memory = Memory::with_capacity(2020);
     numbers
         .iter()
         .enumerate()
@@ -174,13 +207,7 @@ pub fn determine_the_2020th_number_spoken(numbers: &[u32]) -> u32 {
 
 #[aoc(day15, part2)]
 pub fn determine_the_30millionsth_number_spoken(numbers: &[u32]) -> u32 {
-    let mut memory = Memory::new();
-    numbers
-        .iter()
-        .enumerate()
-        .for_each(|(turn, num)| memory.add_spoken_number(turn as u32 + 1, *num));
-    let remaining_turns = 30_000_000 - memory.last_turn();
-    memory.take(remaining_turns as usize).last().unwrap_or(0)
+    nth(numbers, 30_000_000).unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -90,6 +90,59 @@ fn determine_the_2020th_number_spoken_in_puzzle_input() {
     assert_eq!(num2020, 211);
 }
 
+#[test]
+fn memory_iterates_over_numbers_spoken_in_example1() {
+    let mut memory = Memory::new();
+    [0, 3, 6]
+        .iter()
+        .enumerate()
+        .for_each(|(turn, num)| memory.add_spoken_number(turn as u32 + 1, *num));
+
+    let spoken = memory.by_ref().take(7).collect::<Vec<_>>();
+
+    assert_eq!(spoken, vec![0, 3, 3, 1, 0, 4, 0]);
+    assert_eq!(memory.last_turn(), 10);
+    assert_eq!(memory.last_spoken_number(), 0);
+}
+
+#[test]
+fn nth_number_spoken_in_example1() {
+    let numbers = parse_starting_numbers(EXAMPLE1);
+
+    let spoken = (1..=10)
+        .map(|turn| nth(&numbers, turn).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+    assert_eq!(nth(&numbers, 2020), Some(436));
+    assert_eq!(nth(&numbers, 0), None);
+    assert_eq!(nth(&[], 5), None);
+    assert_eq!(nth(&numbers, u32::MAX as usize + 1), None);
+}
+
+#[test]
+fn nth_number_spoken_with_large_starting_number() {
+    assert_eq!(nth(&[100, 1], 3), Some(0));
+    assert_eq!(nth(&[100, 1], 4), Some(0));
+    assert_eq!(nth(&[100, 1], 5), Some(1));
+    assert_eq!(nth(&[100, 1], 6), Some(3));
+    assert_eq!(nth(&[100, 1], 7), Some(0));
+}
+
+#[test]
+fn nth_number_spoken_agrees_with_memory_in_puzzle_input() {
+    let numbers = parse_starting_numbers(INPUT);
+    let mut memory = Memory::new();
+    numbers
+        .iter()
+        .enumerate()
+        .for_each(|(turn, num)| memory.add_spoken_number(turn as u32 + 1, *num));
+
+    for turn in numbers.len() + 1..=3000 {
+        assert_eq!(nth(&numbers, turn), memory.next());
+    }
+}
+
 #[test]
 fn determine_the_30millionsth_number_spoken_in_example1() {
     let numbers = parse_starting_numbers(EXAMPLE1);
@@ -113,9 +113,7 @@
 //! [Advent of Code 2020 - Day 16](https://adventofcode.com/2020/day/16)
 
 use hashbrown::HashMap;
-use std::collections::hash_map::RandomState;
-use std::collections::HashSet;
-use std::iter::FromIterator;
+use std::collections::VecDeque;
 use std::ops::RangeInclusive;
 use std::str::FromStr;
 
@@ -232,62 +230,225 @@ pub fn ticket_scanning_error_rate(ticket_notes: &TicketNotes) -> u32 {
         .sum()
 }
 
-fn determine_field_positions(ticket_notes: &TicketNotes) -> HashMap<String, usize> {
-    let valid_nearby_tickets = ticket_notes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldAssignmentError {
+    /// The listed fields can not all be assigned to distinct columns.
+    Impossible(Vec<String>),
+    /// The listed fields can be assigned to more than one column.
+    Ambiguous(Vec<String>),
+}
+
+fn valid_nearby_tickets(ticket_notes: &TicketNotes) -> Vec<&Ticket> {
+    ticket_notes
         .nearby_tickets
         .iter()
-        .filter(|ticket| {
-            !ticket.values.iter().any(|val| {
-                ticket_notes
-                    .fields
-                    .iter()
-                    .all(|field| !field.is_valid(*val))
-            })
+        .filter(|ticket| find_invalid_values_in_ticket(ticket, &ticket_notes.fields).is_empty())
+        .collect()
+}
+
+/// Lists for each field the columns in which all valid nearby tickets have a
+/// valid value for this field.
+fn candidate_columns(ticket_notes: &TicketNotes) -> Vec<Vec<usize>> {
+    let valid_nearby_tickets = valid_nearby_tickets(ticket_notes);
+    let num_columns = ticket_notes.my_ticket.values.len();
+
+    ticket_notes
+        .fields
+        .iter()
+        .map(|field| {
+            (0..num_columns)
+                .filter(|column| {
+                    valid_nearby_tickets.iter().all(|ticket| {
+                        ticket
+                            .values
+                            .get(*column)
+                            .is_none_or(|value| field.is_valid(*value))
+                    })
+                })
+                .collect()
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
 
-    let num_fields = ticket_notes.fields.len();
+const UNREACHABLE: usize = usize::MAX;
 
-    let mut invalid_positions: HashMap<usize, HashSet<String>> = HashMap::with_capacity(num_fields);
-    for ticket in &valid_nearby_tickets {
-        for (position, value) in ticket.values.iter().enumerate() {
-            let position_entry = invalid_positions
-                .entry(position)
-                .or_insert_with(HashSet::new);
-            for field in &ticket_notes.fields {
-                if !field.is_valid(*value) {
-                    position_entry.insert(field.label.clone());
+/// Maximum matching of fields to columns, found by the Hopcroft-Karp
+/// algorithm.
+#[derive(Debug)]
+struct FieldMatching<'a> {
+    candidates: &'a [Vec<usize>],
+    column_of_field: Vec<Option<usize>>,
+    field_of_column: Vec<Option<usize>>,
+    layers: Vec<usize>,
+}
+
+impl<'a> FieldMatching<'a> {
+    fn new(candidates: &'a [Vec<usize>], num_columns: usize) -> Self {
+        let mut matching = Self {
+            candidates,
+            column_of_field: vec![None; candidates.len()],
+            field_of_column: vec![None; num_columns],
+            layers: vec![UNREACHABLE; candidates.len()],
+        };
+        while matching.layer_free_fields() {
+            for field in 0..candidates.len() {
+                if matching.column_of_field[field].is_none() {
+                    matching.augment(field);
                 }
             }
         }
+        matching
     }
 
-    debug_assert_eq!(invalid_positions.len(), num_fields);
+    /// Layers the fields by the length of the shortest alternating path from a
+    /// free field. Returns whether any augmenting path exists.
+    fn layer_free_fields(&mut self) -> bool {
+        let mut queue = VecDeque::with_capacity(self.candidates.len());
+        for (field, column) in self.column_of_field.iter().enumerate() {
+            if column.is_none() {
+                self.layers[field] = 0;
+                queue.push_back(field);
+            } else {
+                self.layers[field] = UNREACHABLE;
+            }
+        }
+        let mut found_augmenting_path = false;
+        while let Some(field) = queue.pop_front() {
+            for column in &self.candidates[field] {
+                match self.field_of_column[*column] {
+                    None => found_augmenting_path = true,
+                    Some(next) if self.layers[next] == UNREACHABLE => {
+                        self.layers[next] = self.layers[field] + 1;
+                        queue.push_back(next);
+                    },
+                    Some(_) => {},
+                }
+            }
+        }
+        found_augmenting_path
+    }
 
-    let mut field_positions = HashMap::with_capacity(num_fields);
-    let mut remaining_fields = HashSet::<_, RandomState>::from_iter(
-        ticket_notes.fields.iter().map(|fld| fld.label.clone()),
-    );
-    while !remaining_fields.is_empty() {
-        for (position, invalid_fields) in &invalid_positions {
-            let mut valid_fields = remaining_fields
-                .difference(invalid_fields)
-                .cloned()
-                .collect::<Vec<_>>();
-            if valid_fields.len() == 1 {
-                remaining_fields.remove(&valid_fields[0]);
-                field_positions.insert(valid_fields.remove(0), *position);
-                break;
+    fn augment(&mut self, field: usize) -> bool {
+        for column in self.candidates[field].iter().copied() {
+            let augmented = match self.field_of_column[column] {
+                None => true,
+                Some(next) => self.layers[next] == self.layers[field] + 1 && self.augment(next),
+            };
+            if augmented {
+                self.column_of_field[field] = Some(column);
+                self.field_of_column[column] = Some(field);
+                return true;
             }
         }
+        self.layers[field] = UNREACHABLE;
+        false
     }
 
-    field_positions
+    /// Fields reachable by alternating paths from the unmatched fields. These
+    /// fields have fewer candidate columns than there are fields in the set.
+    fn conflicting_fields(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.candidates.len()];
+        let mut open = Vec::new();
+        for (field, column) in self.column_of_field.iter().enumerate() {
+            if column.is_none() {
+                visited[field] = true;
+                open.push(field);
+            }
+        }
+        while let Some(field) = open.pop() {
+            for column in &self.candidates[field] {
+                if let Some(next) = self.field_of_column[*column] {
+                    if !visited[next] {
+                        visited[next] = true;
+                        open.push(next);
+                    }
+                }
+            }
+        }
+        (0..visited.len()).filter(|field| visited[*field]).collect()
+    }
+
+    /// Fields that can swap their column along an alternating cycle or move
+    /// to an unused column along an alternating path.
+    fn ambiguous_fields(&self) -> Vec<usize> {
+        let num_fields = self.candidates.len();
+        let mut alternatives = vec![Vec::new(); num_fields];
+        let mut has_free_column = vec![false; num_fields];
+        for (field, candidates) in self.candidates.iter().enumerate() {
+            for column in candidates {
+                if Some(*column) == self.column_of_field[field] {
+                    continue;
+                }
+                match self.field_of_column[*column] {
+                    Some(other) => alternatives[field].push(other),
+                    None => has_free_column[field] = true,
+                }
+            }
+        }
+        (0..num_fields)
+            .filter(|field| {
+                let mut visited = vec![false; num_fields];
+                let mut open = vec![*field];
+                while let Some(current) = open.pop() {
+                    if has_free_column[current] {
+                        return true;
+                    }
+                    for next in &alternatives[current] {
+                        if next == field {
+                            return true;
+                        }
+                        if !visited[*next] {
+                            visited[*next] = true;
+                            open.push(*next);
+                        }
+                    }
+                }
+                false
+            })
+            .collect()
+    }
+}
+
+fn labels_of(fields: &[Field], indices: Vec<usize>) -> Vec<String> {
+    indices
+        .into_iter()
+        .map(|index| fields[index].label.clone())
+        .collect()
+}
+
+fn determine_field_positions(
+    ticket_notes: &TicketNotes,
+) -> Result<HashMap<String, usize>, FieldAssignmentError> {
+    let candidates = candidate_columns(ticket_notes);
+    let num_columns = ticket_notes.my_ticket.values.len();
+    let matching = FieldMatching::new(&candidates, num_columns);
+
+    if matching.column_of_field.iter().any(Option::is_none) {
+        return Err(FieldAssignmentError::Impossible(labels_of(
+            &ticket_notes.fields,
+            matching.conflicting_fields(),
+        )));
+    }
+    let ambiguous_fields = matching.ambiguous_fields();
+    if !ambiguous_fields.is_empty() {
+        return Err(FieldAssignmentError::Ambiguous(labels_of(
+            &ticket_notes.fields,
+            ambiguous_fields,
+        )));
+    }
+
+    Ok(ticket_notes
+        .fields
+        .iter()
+        .zip(matching.column_of_field)
+        .filter_map(|(field, column)| column.map(|column| (field.label.clone(), column)))
+        .collect())
 }
 
 #[aoc(day16, part2)]
 pub fn product_of_departure_values_in_my_ticket(ticket_notes: &TicketNotes) -> u64 {
-    let field_positions = determine_field_positions(ticket_notes);
+    let field_positions = determine_field_positions(ticket_notes)
+        .unwrap_or_else(|err| panic!("can not determine field positions: {:?}", err));
     ticket_notes
         .fields
         .iter()
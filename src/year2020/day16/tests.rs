@@ -114,7 +114,7 @@ fn ticket_scanning_error_rate_in_puzzle_input() {
 fn determine_field_positions_in_example2() {
     let notes = parse_ticket_notes(EXAMPLE2);
 
-    let field_positions = determine_field_positions(&notes);
+    let field_positions = determine_field_positions(&notes).unwrap();
 
     assert_eq!(field_positions["row"], 0);
     assert_eq!(field_positions["class"], 1);
@@ -125,7 +125,7 @@ fn determine_field_positions_in_example2() {
 fn determine_field_positions_in_puzzle_input() {
     let notes = parse_ticket_notes(INPUT);
 
-    let field_positions = determine_field_positions(&notes);
+    let field_positions = determine_field_positions(&notes).unwrap();
 
     assert_eq!(field_positions["departure time"], 0);
     assert_eq!(field_positions["departure track"], 1);
@@ -149,6 +149,62 @@ fn determine_field_positions_in_puzzle_input() {
     assert_eq!(field_positions["seat"], 19);
 }
 
+#[test]
+fn determine_field_positions_with_interchangeable_fields() {
+    let notes = parse_ticket_notes(
+        "\
+class: 0-9 or 20-29
+row: 0-9 or 20-29
+seat: 10-19 or 30-39
+
+your ticket:
+1,2,10
+
+nearby tickets:
+1,2,10
+3,24,31
+",
+    );
+
+    let field_positions = determine_field_positions(&notes);
+
+    assert_eq!(
+        field_positions,
+        Err(FieldAssignmentError::Ambiguous(vec![
+            "class".into(),
+            "row".into()
+        ]))
+    );
+}
+
+#[test]
+fn determine_field_positions_with_too_few_columns_for_fields() {
+    let notes = parse_ticket_notes(
+        "\
+class: 0-4 or 20-29
+row: 0-4 or 20-29
+seat: 5-9 or 30-39
+
+your ticket:
+1,5,6
+
+nearby tickets:
+1,5,6
+22,7,38
+",
+    );
+
+    let field_positions = determine_field_positions(&notes);
+
+    assert_eq!(
+        field_positions,
+        Err(FieldAssignmentError::Impossible(vec![
+            "class".into(),
+            "row".into()
+        ]))
+    );
+}
+
 #[test]
 fn product_of_departure_values_in_my_ticket_in_puzzle_input() {
     let notes = parse_ticket_notes(INPUT);
@@ -190,8 +190,7 @@
 //!
 //! [Advent of Code 2020 - Day 19](https://adventofcode.com/2020/day/19)
 
-use hashbrown::HashMap;
-use std::iter::FromIterator;
+use hashbrown::{HashMap, HashSet};
 use std::num::ParseIntError;
 use std::str::FromStr;
 
@@ -204,113 +203,562 @@ pub enum Expr {
     /// Matches the left sequence of sub-patterns or the right sequence of sub-
     /// pattern.
     Or(Vec<usize>, Vec<usize>),
+    /// Placeholder for a rule id that is not defined. Never matches.
+    Undefined,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct State {
-    id: usize,
-    op: Logic,
+const UNDEFINED: &Expr = &Expr::Undefined;
+
+fn rule_at(rules: &[Expr], id: usize) -> &Expr {
+    rules.get(id).unwrap_or(UNDEFINED)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Logic {
-    And,
-    OrLeft { start: usize, prev_result: bool },
-    OrRight { prev_result: bool },
+fn sub_rules(expr: &Expr) -> impl Iterator<Item = &usize> {
+    let (left, right): (&[usize], &[usize]) = match expr {
+        Expr::Seq(seq) => (seq, &[]),
+        Expr::Or(left, right) => (left, right),
+        Expr::Char(_) | Expr::Undefined => (&[], &[]),
+    };
+    left.iter().chain(right.iter())
 }
 
+/// Whether any rule reachable from rule 0 refers to itself.
+fn is_recursive(rules: &[Expr]) -> bool {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        Visiting,
+        Done,
+    }
+
+    fn visit(rules: &[Expr], id: usize, marks: &mut [Mark]) -> bool {
+        if id >= marks.len() {
+            return false;
+        }
+        match marks[id] {
+            Mark::Visiting => return true,
+            Mark::Done => return false,
+            Mark::New => {},
+        }
+        marks[id] = Mark::Visiting;
+        for sub_id in sub_rules(&rules[id]) {
+            if visit(rules, *sub_id, marks) {
+                return true;
+            }
+        }
+        marks[id] = Mark::Done;
+        false
+    }
+
+    !rules.is_empty() && visit(rules, 0, &mut vec![Mark::New; rules.len()])
+}
+
+/// Nondeterministic automaton built from non-recursive rules by Thompson's
+/// construction.
+///
+/// Every rule is compiled into a fragment once. A reference to a rule is a
+/// call into its fragment that remembers the state to return to, so rules
+/// used in many places do not multiply the number of states. Each state has
+/// at most one character transition or call. The epsilon closure of every
+/// state within its fragment is computed once at construction.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Pattern {
-    rules: Vec<Expr>,
+struct Automaton {
+    transitions: Vec<Option<(char, usize)>>,
+    epsilons: Vec<Vec<usize>>,
+    /// The start state of the called fragment and the state to return to
+    calls: Vec<Option<(usize, usize)>>,
+    /// Whether the state is the end of a fragment
+    returns: Vec<bool>,
+    closures: Vec<Vec<usize>>,
+    start: usize,
+    accept: usize,
 }
 
-impl Pattern {
-    pub fn new(rules: Vec<Expr>) -> Self {
-        Self { rules }
+impl Automaton {
+    fn compile(rules: &[Expr]) -> Self {
+        let mut automaton = Self {
+            transitions: Vec::new(),
+            epsilons: Vec::new(),
+            calls: Vec::new(),
+            returns: Vec::new(),
+            closures: Vec::new(),
+            start: 0,
+            accept: 0,
+        };
+        let (start, accept) = if rules.is_empty() {
+            let state = automaton.add_state();
+            (state, state)
+        } else {
+            automaton.fragment(rules, 0, &mut vec![None; rules.len()])
+        };
+        automaton.start = start;
+        automaton.accept = accept;
+        automaton.closures = (0..automaton.transitions.len())
+            .map(|state| automaton.epsilon_closure(state))
+            .collect();
+        automaton
     }
 
-    pub fn patch(&mut self, id: usize, rule: Expr) {
-        self.rules[id] = rule;
+    fn add_state(&mut self) -> usize {
+        self.transitions.push(None);
+        self.epsilons.push(Vec::new());
+        self.calls.push(None);
+        self.returns.push(false);
+        self.transitions.len() - 1
     }
 
-    pub fn matches(&self, text: &str) -> bool {
-        if self.rules.is_empty() {
-            return text.is_empty();
+    /// Returns the start and end state of the fragment of the rule, building
+    /// it if it has not been built yet.
+    fn fragment(
+        &mut self,
+        rules: &[Expr],
+        id: usize,
+        fragments: &mut [Option<(usize, usize)>],
+    ) -> (usize, usize) {
+        if let Some(fragment) = fragments.get(id).copied().flatten() {
+            return fragment;
         }
-        use Logic::*;
-        let chars = text.chars().collect::<Vec<_>>();
-        let mut result = true;
-        let mut current = 0;
-        let mut open = vec![State { id: 0, op: And }];
-        let mut prev_or_ops = vec![];
-        while let Some(State { id, op }) = open.pop() {
-            match &self.rules[id] {
-                Expr::Char(c) => {
-                    if chars[current] != *c {
-                        result = false;
-                        // go back to previous OR operation
-                        if let Some(prev_or_op) = prev_or_ops.pop() {
-                            open.drain(prev_or_op..);
-                        } else {
-                            return false;
-                        }
+        let (start, end) = match rule_at(rules, id) {
+            Expr::Char(c) => {
+                let start = self.add_state();
+                let end = self.add_state();
+                self.transitions[start] = Some((*c, end));
+                (start, end)
+            },
+            Expr::Seq(seq) => self.sequence(rules, seq, fragments),
+            Expr::Or(left_seq, right_seq) => {
+                let start = self.add_state();
+                let end = self.add_state();
+                for seq in [left_seq, right_seq] {
+                    let (seq_start, seq_end) = self.sequence(rules, seq, fragments);
+                    self.epsilons[start].push(seq_start);
+                    self.epsilons[seq_end].push(end);
+                }
+                (start, end)
+            },
+            Expr::Undefined => (self.add_state(), self.add_state()),
+        };
+        self.returns[end] = true;
+        if let Some(fragment) = fragments.get_mut(id) {
+            *fragment = Some((start, end));
+        }
+        (start, end)
+    }
+
+    fn sequence(
+        &mut self,
+        rules: &[Expr],
+        seq: &[usize],
+        fragments: &mut [Option<(usize, usize)>],
+    ) -> (usize, usize) {
+        let start = self.add_state();
+        let mut end = start;
+        for id in seq {
+            let next = self.add_state();
+            match rule_at(rules, *id) {
+                // a single character is cheaper to match in place than to call
+                Expr::Char(c) => self.transitions[end] = Some((*c, next)),
+                _ => {
+                    let (sub_start, _) = self.fragment(rules, *id, fragments);
+                    self.calls[end] = Some((sub_start, next));
+                },
+            }
+            end = next;
+        }
+        (start, end)
+    }
+
+    fn epsilon_closure(&self, state: usize) -> Vec<usize> {
+        let mut closure = vec![state];
+        let mut visited = HashSet::new();
+        visited.insert(state);
+        let mut index = 0;
+        while index < closure.len() {
+            for next in &self.epsilons[closure[index]] {
+                if visited.insert(*next) {
+                    closure.push(*next);
+                }
+            }
+            index += 1;
+        }
+        closure
+    }
+
+    /// Adds the states reachable from the state without reading a character,
+    /// following calls into fragments and returns out of them.
+    fn close(
+        &self,
+        state: usize,
+        stack: usize,
+        stacks: &mut CallStacks,
+        seen: &mut HashSet<(usize, usize)>,
+        reached: &mut Vec<(usize, usize)>,
+    ) {
+        let mut pending = vec![(state, stack)];
+        while let Some((state, stack)) = pending.pop() {
+            for &closed in &self.closures[state] {
+                if !seen.insert((closed, stack)) {
+                    continue;
+                }
+                reached.push((closed, stack));
+                if let Some((sub_start, return_state)) = self.calls[closed] {
+                    pending.push((sub_start, stacks.push(return_state, stack)));
+                }
+                if self.returns[closed] {
+                    if let Some((return_state, caller)) = stacks.pop(stack) {
+                        pending.push((return_state, caller));
                     }
-                    current += 1;
                 }
+            }
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let mut stacks = CallStacks::new();
+        let mut seen = HashSet::new();
+        let mut current = Vec::new();
+        self.close(
+            self.start,
+            CallStacks::EMPTY,
+            &mut stacks,
+            &mut seen,
+            &mut current,
+        );
+        let mut next = Vec::with_capacity(current.len());
+        for c in text.chars() {
+            seen.clear();
+            next.clear();
+            for &(state, stack) in &current {
+                match self.transitions[state] {
+                    Some((expected, target)) if expected == c => {
+                        self.close(target, stack, &mut stacks, &mut seen, &mut next);
+                    },
+                    _ => {},
+                }
+            }
+            if next.is_empty() {
+                return false;
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        current.contains(&(self.accept, CallStacks::EMPTY))
+    }
+}
+
+/// The stacks of return states while matching a message with an automaton.
+///
+/// Every stack is identified by an index and shares its tail with the stack
+/// of its caller, so equal stacks have the same index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CallStacks {
+    /// The return state and the index of the rest of the stack
+    frames: Vec<(usize, usize)>,
+    indices: HashMap<(usize, usize), usize>,
+}
+
+impl CallStacks {
+    const EMPTY: usize = 0;
+
+    fn new() -> Self {
+        Self {
+            frames: vec![(0, Self::EMPTY)],
+            indices: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, return_state: usize, stack: usize) -> usize {
+        let frames = &mut self.frames;
+        *self
+            .indices
+            .entry((return_state, stack))
+            .or_insert_with(|| {
+                frames.push((return_state, stack));
+                frames.len() - 1
+            })
+    }
+
+    fn pop(&self, stack: usize) -> Option<(usize, usize)> {
+        (stack != Self::EMPTY).then(|| self.frames[stack])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: usize,
+    alternative: usize,
+    dot: usize,
+    origin: usize,
+}
+
+impl Item {
+    fn advance(self) -> Self {
+        Self {
+            dot: self.dot + 1,
+            ..self
+        }
+    }
+}
+
+/// Earley recognizer for rule sets that contain loops.
+///
+/// Rules with a character literal are treated as terminals. An additional
+/// start rule with the single alternative `0` is appended to the grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Recognizer {
+    alternatives: Vec<Vec<Vec<usize>>>,
+    terminals: Vec<Option<char>>,
+    nullable: Vec<bool>,
+    start: usize,
+}
+
+impl Recognizer {
+    fn compile(rules: &[Expr]) -> Self {
+        // rule ids that are referenced but not defined get no alternatives
+        let num_rules = rules
+            .iter()
+            .flat_map(sub_rules)
+            .map(|id| id + 1)
+            .chain(Some(rules.len()))
+            .max()
+            .unwrap_or(0);
+        let mut alternatives = Vec::with_capacity(num_rules + 1);
+        let mut terminals = Vec::with_capacity(num_rules + 1);
+        for id in 0..num_rules {
+            match rule_at(rules, id) {
+                Expr::Char(c) => {
+                    alternatives.push(vec![]);
+                    terminals.push(Some(*c));
+                },
                 Expr::Seq(seq) => {
-                    open.extend(seq.iter().rev().map(|id| State { id: *id, op: And }));
+                    alternatives.push(vec![seq.clone()]);
+                    terminals.push(None);
+                },
+                Expr::Or(left_seq, right_seq) => {
+                    alternatives.push(vec![left_seq.clone(), right_seq.clone()]);
+                    terminals.push(None);
+                },
+                Expr::Undefined => {
+                    alternatives.push(vec![]);
+                    terminals.push(None);
+                },
+            }
+        }
+        let start = alternatives.len();
+        alternatives.push(if rules.is_empty() {
+            vec![vec![]]
+        } else {
+            vec![vec![0]]
+        });
+        terminals.push(None);
+        let mut recognizer = Self {
+            alternatives,
+            terminals,
+            nullable: Vec::new(),
+            start,
+        };
+        recognizer.nullable = recognizer.nullable_rules();
+        recognizer
+    }
+
+    fn nullable_rules(&self) -> Vec<bool> {
+        let mut nullable = vec![false; self.alternatives.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (rule, alternatives) in self.alternatives.iter().enumerate() {
+                if !nullable[rule]
+                    && alternatives
+                        .iter()
+                        .any(|seq| seq.iter().all(|id| nullable[*id]))
+                {
+                    nullable[rule] = true;
+                    changed = true;
                 }
-                Expr::Or(left_seq, right_seq) => match op {
-                    And => {
-                        open.push(State {
-                            id,
-                            op: OrLeft {
-                                start: current,
-                                prev_result: result,
-                            },
-                        });
-                        prev_or_ops.push(open.len());
-                        open.extend(left_seq.iter().rev().map(|id| State { id: *id, op: And }));
-                        result = true;
-                    }
-                    OrLeft { start, prev_result } => {
-                        if result {
-                            prev_or_ops.pop();
-                            result = prev_result;
+            }
+        }
+        nullable
+    }
+
+    fn next_symbol(&self, item: Item) -> Option<usize> {
+        self.alternatives[item.rule][item.alternative]
+            .get(item.dot)
+            .copied()
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut chart: Vec<Vec<Item>> = vec![Vec::new(); chars.len() + 1];
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); chars.len() + 1];
+
+        fn add(chart: &mut [Vec<Item>], seen: &mut [HashSet<Item>], position: usize, item: Item) {
+            if seen[position].insert(item) {
+                chart[position].push(item);
+            }
+        }
+
+        for alternative in 0..self.alternatives[self.start].len() {
+            let item = Item {
+                rule: self.start,
+                alternative,
+                dot: 0,
+                origin: 0,
+            };
+            add(&mut chart, &mut seen, 0, item);
+        }
+
+        for position in 0..=chars.len() {
+            let mut index = 0;
+            while index < chart[position].len() {
+                let item = chart[position][index];
+                index += 1;
+                match self.next_symbol(item) {
+                    Some(symbol) => {
+                        if let Some(c) = self.terminals[symbol] {
+                            // scan
+                            if chars.get(position) == Some(&c) {
+                                add(&mut chart, &mut seen, position + 1, item.advance());
+                            }
                         } else {
-                            open.push(State {
-                                id,
-                                op: OrRight { prev_result },
-                            });
-                            prev_or_ops.push(open.len());
-                            open.extend(
-                                right_seq.iter().rev().map(|id| State { id: *id, op: And }),
-                            );
-                            current = start;
-                            result = true;
+                            // predict
+                            for alternative in 0..self.alternatives[symbol].len() {
+                                let predicted = Item {
+                                    rule: symbol,
+                                    alternative,
+                                    dot: 0,
+                                    origin: position,
+                                };
+                                add(&mut chart, &mut seen, position, predicted);
+                            }
+                            if self.nullable[symbol] {
+                                add(&mut chart, &mut seen, position, item.advance());
+                            }
                         }
-                    }
-                    OrRight { prev_result } => {
-                        if result {
-                            prev_or_ops.pop();
-                            result = prev_result;
-                        } else {
-                            result = false;
-                            // go back to previous OR operation
-                            if let Some(prev_or_op) = prev_or_ops.pop() {
-                                open.drain(prev_or_op..);
-                            } else {
-                                return false;
+                    },
+                    None => {
+                        // complete
+                        let mut waiting = 0;
+                        while waiting < chart[item.origin].len() {
+                            let parent = chart[item.origin][waiting];
+                            waiting += 1;
+                            if self.next_symbol(parent) == Some(item.rule) {
+                                add(&mut chart, &mut seen, position, parent.advance());
                             }
                         }
-                    }
-                },
+                    },
+                }
             }
+            if position < chars.len() && chart[position + 1].is_empty() {
+                return false;
+            }
+        }
+
+        chart[chars.len()].iter().any(|item| {
+            item.rule == self.start && item.origin == 0 && self.next_symbol(*item).is_none()
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Matcher {
+    Automaton(Automaton),
+    Recognizer(Recognizer),
+}
+
+impl Matcher {
+    fn compile(rules: &[Expr]) -> Self {
+        if is_recursive(rules) {
+            Matcher::Recognizer(Recognizer::compile(rules))
+        } else {
+            Matcher::Automaton(Automaton::compile(rules))
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Matcher::Automaton(automaton) => automaton.matches(text),
+            Matcher::Recognizer(recognizer) => recognizer.matches(text),
+        }
+    }
+}
+
+/// A set of message rules compiled for matching.
+///
+/// Rule sets without loops are compiled into a finite automaton, which matches
+/// a message in linear time. Rule sets with loops, like the patched rules of
+/// part 2, are matched by an Earley recognizer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    rules: Vec<Expr>,
+    matcher: Matcher,
+}
+
+impl Pattern {
+    pub fn new(rules: Vec<Expr>) -> Self {
+        let matcher = Matcher::compile(&rules);
+        Self { rules, matcher }
+    }
+
+    pub fn patch(&mut self, id: usize, rule: Expr) {
+        self.rules[id] = rule;
+        self.matcher = Matcher::compile(&self.rules);
+    }
+
+    pub fn is_recursive(&self) -> bool {
+        matches!(self.matcher, Matcher::Recognizer(_))
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        self.matcher.matches(text)
+    }
+
+    /// Returns an anchored regular expression that matches exactly the
+    /// messages matched by this pattern.
+    ///
+    /// Returns `None` if the rules contain loops or if a rule reachable from
+    /// rule 0 is undefined.
+    pub fn to_regex(&self) -> Option<String> {
+        if self.is_recursive() {
+            return None;
         }
-        result && current == chars.len()
+        if self.rules.is_empty() {
+            return Some("^$".into());
+        }
+        let mut cache = vec![None; self.rules.len()];
+        let body = regex_of_rule(&self.rules, 0, &mut cache)?;
+        Some(format!("^{}$", body))
     }
 }
 
+fn regex_of_rule(rules: &[Expr], id: usize, cache: &mut [Option<String>]) -> Option<String> {
+    if let Some(regex) = cache.get(id).and_then(|cached| cached.as_ref()) {
+        return Some(regex.clone());
+    }
+    let regex = match rule_at(rules, id) {
+        Expr::Char(c) => {
+            if "\\.+*?()|[]{}^$#&-~".contains(*c) {
+                format!("\\{}", c)
+            } else {
+                c.to_string()
+            }
+        },
+        Expr::Seq(seq) => regex_of_seq(rules, seq, cache)?,
+        Expr::Or(left_seq, right_seq) => format!(
+            "(?:{}|{})",
+            regex_of_seq(rules, left_seq, cache)?,
+            regex_of_seq(rules, right_seq, cache)?
+        ),
+        Expr::Undefined => return None,
+    };
+    cache[id] = Some(regex.clone());
+    Some(regex)
+}
+
+fn regex_of_seq(rules: &[Expr], seq: &[usize], cache: &mut [Option<String>]) -> Option<String> {
+    seq.iter()
+        .map(|id| regex_of_rule(rules, *id, cache))
+        .collect()
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MonsterMessages {
     pub rules: Vec<Expr>,
@@ -355,27 +803,22 @@ pub fn parse_monster_messages(input: &str) -> MonsterMessages {
             pattern_definitions.insert(id, Expr::Seq(pattern_seq));
         }
     }
-    let mut pattern = Vec::from_iter(pattern_definitions.into_iter());
-    pattern.sort_by_key(|entry| entry.0);
-    debug_assert_eq!(
-        pattern
-            .iter()
-            .enumerate()
-            .filter(|(idx, (id, _))| *idx != *id)
-            .collect::<Vec<_>>(),
-        vec![]
-    );
-    let rules = Vec::from_iter(pattern.into_iter().map(|(_, pat)| pat));
+    let num_rules = pattern_definitions
+        .keys()
+        .max()
+        .map(|max_id| max_id + 1)
+        .unwrap_or(0);
+    let mut rules = vec![Expr::Undefined; num_rules];
+    for (id, pattern) in pattern_definitions {
+        rules[id] = pattern;
+    }
 
     let messages = lines
         .filter(|ln| !ln.is_empty())
         .map(String::from)
         .collect();
 
-    MonsterMessages {
-        rules: rules,
-        messages,
-    }
+    MonsterMessages { rules, messages }
 }
 
 #[aoc(day19, part1)]
@@ -389,7 +832,7 @@ pub fn count_matching_messages(monster_messages: &MonsterMessages) -> usize {
         .count()
 }
 
-//#[aoc(day19, part2)]
+#[aoc(day19, part2)]
 pub fn count_matching_messages_patched_rules(monster_messages: &MonsterMessages) -> usize {
     let mut pattern = Pattern::new(monster_messages.rules.clone());
     pattern.patch(8, Expr::Or(vec![42], vec![42, 8]));
@@ -144,6 +144,106 @@ fn fifth_message_does_not_match_pattern_in_example() {
     assert!(!pattern.matches("aaaabbb"));
 }
 
+#[test]
+fn parse_monster_messages_with_undefined_rule_ids_in_example2() {
+    let monster_messages = parse_monster_messages(EXAMPLE2);
+
+    assert_eq!(monster_messages.rules.len(), 43);
+    assert_eq!(monster_messages.rules[32], Expr::Undefined);
+    assert_eq!(
+        monster_messages.rules[42],
+        Expr::Or(vec![9, 14], vec![10, 1])
+    );
+}
+
+#[test]
+fn pattern_in_example_is_not_recursive() {
+    let monster_messages = parse_monster_messages(EXAMPLE);
+    let pattern = Pattern::new(monster_messages.rules);
+
+    assert!(!pattern.is_recursive());
+}
+
+#[test]
+fn patched_pattern_in_example2_is_recursive() {
+    let monster_messages = parse_monster_messages(EXAMPLE2);
+    let mut pattern = Pattern::new(monster_messages.rules);
+    pattern.patch(8, Expr::Or(vec![42], vec![42, 8]));
+
+    assert!(pattern.is_recursive());
+    assert_eq!(pattern.to_regex(), None);
+}
+
+#[test]
+fn patched_pattern_matches_repeated_rules_in_example2() {
+    let monster_messages = parse_monster_messages(EXAMPLE2);
+    let mut pattern = Pattern::new(monster_messages.rules);
+    pattern.patch(8, Expr::Or(vec![42], vec![42, 8]));
+    pattern.patch(11, Expr::Or(vec![42, 31], vec![42, 11, 31]));
+
+    assert!(pattern.matches("babbbbaabbbbbabbbbbbaabaaabaaa"));
+    assert!(!pattern.matches("abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa"));
+}
+
+#[test]
+fn pattern_to_regex_in_example() {
+    let monster_messages = parse_monster_messages(EXAMPLE);
+    let pattern = Pattern::new(monster_messages.rules);
+
+    assert_eq!(
+        pattern.to_regex(),
+        Some("^a(?:(?:aa|bb)(?:ab|ba)|(?:ab|ba)(?:aa|bb))b$".into())
+    );
+}
+
+#[test]
+fn pattern_with_undefined_rule_has_no_regex() {
+    let pattern = Pattern::new(vec![Expr::Seq(vec![1, 2]), Expr::Char('a')]);
+
+    assert_eq!(pattern.to_regex(), None);
+    assert!(!pattern.matches("a"));
+}
+
+#[test]
+fn pattern_with_deeply_nested_shared_rules() {
+    let nested = |depth: usize| {
+        let mut rules: Vec<Expr> = (0..depth)
+            .map(|id| Expr::Seq(vec![id + 1, id + 1]))
+            .collect();
+        rules.push(Expr::Char('a'));
+        Pattern::new(rules)
+    };
+
+    assert!(nested(10).matches(&"a".repeat(1024)));
+    assert!(!nested(10).matches(&"a".repeat(1023)));
+    assert!(!nested(10).matches(&"a".repeat(1025)));
+    assert!(!nested(60).matches("aaaa"));
+}
+
+#[test]
+fn shared_rule_returns_to_the_rule_referring_to_it() {
+    let pattern = Pattern::new(vec![
+        Expr::Or(vec![1, 2], vec![2, 1]),
+        Expr::Seq(vec![3, 3]),
+        Expr::Char('b'),
+        Expr::Char('a'),
+    ]);
+
+    assert!(pattern.matches("aab"));
+    assert!(pattern.matches("baa"));
+    assert!(!pattern.matches("aba"));
+    assert!(!pattern.matches("aa"));
+}
+
+#[test]
+fn empty_pattern_matches_empty_message() {
+    let pattern = Pattern::new(vec![]);
+
+    assert!(pattern.matches(""));
+    assert!(!pattern.matches("a"));
+    assert_eq!(pattern.to_regex(), Some("^$".into()));
+}
+
 #[test]
 fn count_matching_messages_in_example() {
     let monster_messages = parse_monster_messages(EXAMPLE);
@@ -187,6 +287,5 @@ fn count_matching_messages_patched_rules_in_puzzle_input() {
 
     let count = count_matching_messages_patched_rules(&monster_messages);
 
-    assert_ne!(count, 14);
-    assert_eq!(count, 198);
+    assert_eq!(count, 372);
 }
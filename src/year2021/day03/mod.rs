@@ -130,6 +130,7 @@
 
 use bitvec::field::BitField;
 use bitvec::vec::BitVec;
+use std::cmp::Ordering;
 
 #[aoc_generator(day3)]
 pub fn parse(input: &str) -> Vec<BitVec> {
@@ -175,44 +176,139 @@ pub fn power_consumption(diagnostic_report: &[BitVec]) -> u32 {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Filter {
+pub enum Filter {
     MostCommon,
     LeastCommon,
 }
 
-fn entries_with_bit_at_position(filter: Filter, index: usize, entries: &[BitVec]) -> Vec<BitVec> {
-    match (filter, most_common_value(index, entries)) {
-        (Filter::MostCommon, true) | (Filter::LeastCommon, false) => entries
-            .iter()
-            .filter(|&bits| bits[index])
-            .cloned()
-            .collect(),
-        (Filter::MostCommon, false) | (Filter::LeastCommon, true) => entries
-            .iter()
-            .filter(|&bits| !bits[index])
-            .cloned()
-            .collect(),
+/// Selects the value of a bit to keep from the remaining entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitCriterion {
+    pub filter: Filter,
+    /// The value to keep if `0` and `1` are equally common
+    pub on_tie: bool,
+}
+
+impl BitCriterion {
+    pub const OXYGEN_GENERATOR: BitCriterion = BitCriterion {
+        filter: Filter::MostCommon,
+        on_tie: true,
+    };
+
+    pub const CO2_SCRUBBER: BitCriterion = BitCriterion {
+        filter: Filter::LeastCommon,
+        on_tie: false,
+    };
+
+    /// Returns the value to keep given the number of remaining entries with
+    /// a `0` and a `1`. A value no entry has is never kept.
+    fn select(&self, zeros: usize, ones: usize) -> bool {
+        if zeros == 0 || ones == 0 {
+            return ones > 0;
+        }
+        match (self.filter, ones.cmp(&zeros)) {
+            (_, Ordering::Equal) => self.on_tie,
+            (Filter::MostCommon, ordering) => ordering == Ordering::Greater,
+            (Filter::LeastCommon, ordering) => ordering == Ordering::Less,
+        }
     }
 }
 
-fn find_entry(filter: Filter, entries: &[BitVec]) -> Option<BitVec> {
-    let entry_len = entries.get(0)?.len();
-    let mut selected_entries = entries.to_vec();
-    for index in (0..entry_len).rev() {
-        if selected_entries.len() == 1 {
-            break;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Node {
+    /// The nodes for the next bit being `0` and `1`
+    children: [Option<usize>; 2],
+    /// The number of entries in the subtree of this node
+    count: usize,
+}
+
+/// A binary trie of the entries of a diagnostic report, starting with the
+/// most significant bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitTrie {
+    entry_len: usize,
+    nodes: Vec<Node>,
+}
+
+impl BitTrie {
+    /// Builds a trie of the entries, which must all have the same length.
+    pub fn new(entries: &[BitVec]) -> Self {
+        let mut trie = Self {
+            entry_len: entries.first().map_or(0, |bits| bits.len()),
+            nodes: vec![Node::default()],
+        };
+        for entry in entries {
+            trie.insert(entry);
+        }
+        trie
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the entry does not have the length of the entries in the trie.
+    pub fn insert(&mut self, entry: &BitVec) {
+        assert_eq!(
+            entry.len(),
+            self.entry_len,
+            "entry does not have the length of the other entries"
+        );
+        let mut node = 0;
+        self.nodes[node].count += 1;
+        for index in (0..self.entry_len).rev() {
+            let bit = usize::from(entry[index]);
+            node = match self.nodes[node].children[bit] {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children[bit] = Some(child);
+                    child
+                },
+            };
+            self.nodes[node].count += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes[0].count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn count(&self, node: Option<usize>) -> usize {
+        node.map_or(0, |node| self.nodes[node].count)
+    }
+
+    /// Finds the entry by selecting the value of each bit from the most
+    /// significant one on among the entries that are left, or `None` if the
+    /// trie is empty. Once a single entry is left it is selected.
+    pub fn find(&self, criterion: BitCriterion) -> Option<BitVec> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut entry = BitVec::repeat(false, self.entry_len);
+        let mut node = 0;
+        for index in (0..self.entry_len).rev() {
+            let [zeros, ones] = self.nodes[node].children;
+            let bit = criterion.select(self.count(zeros), self.count(ones));
+            entry.set(index, bit);
+            node = self.nodes[node].children[usize::from(bit)].expect("selected bit has entries");
         }
-        selected_entries = entries_with_bit_at_position(filter, index, &selected_entries);
+        Some(entry)
     }
-    selected_entries.get(0).cloned()
 }
 
 #[aoc(day3, part2)]
 pub fn life_support_rating(diagnostic_report: &[BitVec]) -> u32 {
-    let oxygen_generator_rating = find_entry(Filter::MostCommon, diagnostic_report)
+    let trie = BitTrie::new(diagnostic_report);
+    let oxygen_generator_rating = trie
+        .find(BitCriterion::OXYGEN_GENERATOR)
         .expect("did not find most common entry")
         .load::<u32>();
-    let co2_scrubber_rating = find_entry(Filter::LeastCommon, diagnostic_report)
+    let co2_scrubber_rating = trie
+        .find(BitCriterion::CO2_SCRUBBER)
         .expect("did not find least common entry")
         .load::<u32>();
     oxygen_generator_rating * co2_scrubber_rating
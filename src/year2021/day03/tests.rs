@@ -83,3 +83,67 @@ mod part2 {
         assert_eq!(life_support_rating, 6124992);
     }
 }
+
+mod bit_trie {
+    use super::*;
+
+    const MOST_COMMON_OR_ZERO: BitCriterion = BitCriterion {
+        filter: Filter::MostCommon,
+        on_tie: false,
+    };
+
+    const LEAST_COMMON_OR_ONE: BitCriterion = BitCriterion {
+        filter: Filter::LeastCommon,
+        on_tie: true,
+    };
+
+    fn find(trie: &BitTrie, criterion: BitCriterion) -> Option<u32> {
+        trie.find(criterion).map(|entry| entry.load::<u32>())
+    }
+
+    #[test]
+    fn find_example1() {
+        let trie = BitTrie::new(&parse(EXAMPLE1));
+
+        assert_eq!(trie.len(), 12);
+        assert_eq!(find(&trie, BitCriterion::OXYGEN_GENERATOR), Some(23));
+        assert_eq!(find(&trie, BitCriterion::CO2_SCRUBBER), Some(10));
+        assert_eq!(find(&trie, MOST_COMMON_OR_ZERO), Some(22));
+        assert_eq!(find(&trie, LEAST_COMMON_OR_ONE), Some(15));
+    }
+
+    #[test]
+    fn find_with_custom_tie_rules() {
+        let trie = BitTrie::new(&parse(INPUT));
+
+        assert_eq!(find(&trie, MOST_COMMON_OR_ZERO), Some(1664));
+        assert_eq!(find(&trie, LEAST_COMMON_OR_ONE), Some(3665));
+    }
+
+    #[test]
+    fn find_in_empty_trie() {
+        let trie = BitTrie::new(&[]);
+
+        assert!(trie.is_empty());
+        assert_eq!(find(&trie, BitCriterion::OXYGEN_GENERATOR), None);
+    }
+
+    #[test]
+    fn find_in_large_report() {
+        let report = (0..1u32 << 18)
+            .map(|value| {
+                let mut entry = BitVec::repeat(false, 18);
+                entry.store(value);
+                entry
+            })
+            .collect::<Vec<_>>();
+
+        let trie = BitTrie::new(&report);
+
+        assert_eq!(
+            find(&trie, BitCriterion::OXYGEN_GENERATOR),
+            Some((1 << 18) - 1)
+        );
+        assert_eq!(find(&trie, BitCriterion::CO2_SCRUBBER), Some(0));
+    }
+}
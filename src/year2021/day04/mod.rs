@@ -103,6 +103,8 @@
 //!
 //! [Advent of Code 2021 - Day 4](https://adventofcode.com/2021/day/4)
 
+use hashbrown::HashMap;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Cell {
     value: u32,
@@ -128,40 +130,58 @@ impl Cell {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Board {
-    cells: [Cell; Board::ROW_LEN * Board::NUM_ROWS],
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
 }
 
+/// Creates a square board.
 impl TryFrom<&[u32]> for Board {
     type Error = String;
 
     fn try_from(values: &[u32]) -> Result<Self, Self::Error> {
-        const BOARD_SIZE: usize = Board::ROW_LEN * Board::NUM_ROWS;
-        if values.len() != BOARD_SIZE {
+        let side = (0..=values.len())
+            .find(|side| side * side >= values.len())
+            .unwrap_or_default();
+        if side * side != values.len() {
             Err(format!(
-                "number of numbers for board ({}) does not match board size ({})",
+                "number of numbers for board ({}) is not a square",
                 values.len(),
-                BOARD_SIZE
             ))
         } else {
-            let mut cells = [Cell {
-                value: 0,
-                marked: false,
-            }; BOARD_SIZE];
-
-            values
-                .iter()
-                .enumerate()
-                .for_each(|(idx, &number)| cells[idx].value = number);
-            Ok(Self { cells })
+            Board::new(side, values)
         }
     }
 }
 
 impl Board {
-    pub const ROW_LEN: usize = 5;
-    pub const NUM_ROWS: usize = 5;
+    /// Creates a board with rows of the given width from the values in row
+    /// major order.
+    pub fn new(width: usize, values: &[u32]) -> Result<Self, String> {
+        if width == 0 || values.is_empty() || !values.len().is_multiple_of(width) {
+            Err(format!(
+                "number of numbers for board ({}) does not fill rows of length {}",
+                values.len(),
+                width
+            ))
+        } else {
+            Ok(Self {
+                width,
+                height: values.len() / width,
+                cells: values.iter().map(|&value| Cell::from(value)).collect(),
+            })
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
 
     pub fn check_number(&mut self, number: u32) -> Option<()> {
         self.cells
@@ -171,12 +191,11 @@ impl Board {
     }
 
     pub fn is_bingo(&self) -> bool {
-        (0..Board::NUM_ROWS).any(|row| {
-            let row_offset = row * Board::ROW_LEN;
-            (0..Board::ROW_LEN).all(|col| self.cells[row_offset + col].is_marked())
-        }) || (0..Board::ROW_LEN).any(|col| {
-            (0..Board::NUM_ROWS).all(|row| self.cells[col + row * Board::ROW_LEN].is_marked())
-        })
+        (0..self.height).any(|row| {
+            let row_offset = row * self.width;
+            (0..self.width).all(|col| self.cells[row_offset + col].is_marked())
+        }) || (0..self.width)
+            .any(|col| (0..self.height).all(|row| self.cells[col + row * self.width].is_marked()))
     }
 
     pub fn score(&self, number: u32) -> u32 {
@@ -190,17 +209,125 @@ impl Board {
     }
 }
 
+/// Which lines of marked numbers make a board win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinningLines {
+    RowsAndColumns,
+    /// Also counts both diagonals of square boards.
+    WithDiagonals,
+}
+
+/// A board that has won the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Win {
+    /// The index of the board in the game.
+    pub board: usize,
+    /// The number that completed a line.
+    pub number: u32,
+    /// The number of numbers drawn so far, starting at 1.
+    pub turn: usize,
+    pub score: u32,
+}
+
+/// Counts the marked cells of each line of a board.
+#[derive(Debug, Clone)]
+struct LineCounts {
+    rows: Vec<usize>,
+    columns: Vec<usize>,
+    diagonal: usize,
+    anti_diagonal: usize,
+}
+
+impl LineCounts {
+    fn new(board: &Board) -> Self {
+        Self {
+            rows: vec![0; board.height],
+            columns: vec![0; board.width],
+            diagonal: 0,
+            anti_diagonal: 0,
+        }
+    }
+
+    /// Counts the marked cell and returns whether it completes a line.
+    fn mark(&mut self, board: &Board, index: usize, lines: WinningLines) -> bool {
+        let (row, col) = (index / board.width, index % board.width);
+        self.rows[row] += 1;
+        self.columns[col] += 1;
+        let mut complete = self.rows[row] == board.width || self.columns[col] == board.height;
+        if lines == WinningLines::WithDiagonals && board.width == board.height {
+            if row == col {
+                self.diagonal += 1;
+                complete |= self.diagonal == board.width;
+            }
+            if row + col + 1 == board.width {
+                self.anti_diagonal += 1;
+                complete |= self.anti_diagonal == board.width;
+            }
+        }
+        complete
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Game {
     pub drawn_numbers: Vec<u32>,
     pub boards: Vec<Board>,
 }
 
+impl Game {
+    /// Plays the game once and returns the boards in the order they win.
+    /// Boards that win on the same number are ordered by their index. Boards
+    /// that never win are not included.
+    pub fn finishing_order(&self, lines: WinningLines) -> Vec<Win> {
+        let mut positions: HashMap<u32, Vec<(usize, usize)>> = HashMap::new();
+        for (board_idx, board) in self.boards.iter().enumerate() {
+            for (cell_idx, cell) in board.cells.iter().enumerate() {
+                positions
+                    .entry(cell.value)
+                    .or_default()
+                    .push((board_idx, cell_idx));
+            }
+        }
+        let mut boards = self.boards.clone();
+        let mut counts = boards.iter().map(LineCounts::new).collect::<Vec<_>>();
+        let mut won = vec![false; boards.len()];
+        let mut winners = Vec::new();
+        let mut order = Vec::new();
+        for (turn, &number) in self.drawn_numbers.iter().enumerate() {
+            for &(board_idx, cell_idx) in positions.get(&number).into_iter().flatten() {
+                let board = &mut boards[board_idx];
+                if won[board_idx] || board.cells[cell_idx].is_marked() {
+                    continue;
+                }
+                board.cells[cell_idx].mark();
+                if counts[board_idx].mark(board, cell_idx, lines) && !winners.contains(&board_idx) {
+                    winners.push(board_idx);
+                }
+            }
+            winners.sort_unstable();
+            for board_idx in winners.drain(..) {
+                won[board_idx] = true;
+                order.push(Win {
+                    board: board_idx,
+                    number,
+                    turn: turn + 1,
+                    score: boards[board_idx].score(number),
+                });
+            }
+            if order.len() == boards.len() {
+                break;
+            }
+        }
+        order
+    }
+}
+
 #[aoc_generator(day4)]
 pub fn parse(input: &str) -> Game {
     let mut drawn_numbers = None;
     let mut boards = Vec::new();
     let mut current_board = Vec::new();
+    let mut board_width = 0;
     for (idx, line) in input.lines().enumerate() {
         if drawn_numbers.is_none() {
             if line.is_empty() {
@@ -220,12 +347,13 @@ pub fn parse(input: &str) -> Game {
             if line.is_empty() {
                 if !current_board.is_empty() {
                     boards.push(
-                        Board::try_from(&current_board[..])
+                        Board::new(board_width, &current_board)
                             .expect(&format!("invalid board at line: {}", idx + 1)),
                     );
                 }
                 current_board = Vec::new();
             }
+            let row_start = current_board.len();
             current_board.extend(
                 line.split(' ')
                     .filter(|part| !part.is_empty())
@@ -235,12 +363,22 @@ pub fn parse(input: &str) -> Game {
                             .expect(&format!("invalid number in board at line: {}", idx + 1))
                     }),
             );
+            let row_width = current_board.len() - row_start;
+            if row_start == 0 {
+                board_width = row_width;
+            } else if row_width != board_width {
+                panic!(
+                    "board row at line {} has {} numbers instead of {}",
+                    idx + 1,
+                    row_width,
+                    board_width
+                );
+            }
         }
     }
     if !current_board.is_empty() {
-        boards.push(
-            Board::try_from(&current_board[..]).expect(&format!("invalid board at end of input")),
-        );
+        boards
+            .push(Board::new(board_width, &current_board).expect("invalid board at end of input"));
     }
     Game {
         drawn_numbers: drawn_numbers.expect("no drawn numbers in input"),
@@ -250,42 +388,17 @@ pub fn parse(input: &str) -> Game {
 
 #[aoc(day4, part1)]
 pub fn score_of_first_winning_board(game: &Game) -> u32 {
-    let mut winning_board = None;
-    let mut boards = game.boards.clone();
-    'bingo: for &number in &game.drawn_numbers {
-        for board in &mut boards {
-            board.check_number(number);
-            if board.is_bingo() {
-                winning_board = Some((number, board.clone()));
-                break 'bingo;
-            }
-        }
-    }
-    winning_board
-        .map(|(last_number, board)| board.score(last_number))
+    game.finishing_order(WinningLines::RowsAndColumns)
+        .first()
+        .map(|win| win.score)
         .expect("no winning board at all!")
 }
 
 #[aoc(day4, part2)]
 pub fn score_of_last_winning_board(game: &Game) -> u32 {
-    let mut winning_board = None;
-    let mut winners = Vec::new();
-    let mut boards = game.boards.clone();
-    for &number in &game.drawn_numbers {
-        for (idx, board) in boards.iter_mut().enumerate() {
-            let marked = board.check_number(number);
-            if marked.is_some() && board.is_bingo() {
-                winners.push(idx);
-            }
-        }
-        winners.sort_by(|a, b| b.cmp(a));
-        for &index in &winners {
-            winning_board = Some((number, boards.remove(index)));
-        }
-        winners.clear();
-    }
-    winning_board
-        .map(|(last_number, board)| board.score(last_number))
+    game.finishing_order(WinningLines::RowsAndColumns)
+        .last()
+        .map(|win| win.score)
         .expect("no winning board at all!")
 }
 
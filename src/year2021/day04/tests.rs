@@ -64,6 +64,12 @@ mod parse_input {
             }
         )
     }
+
+    #[test]
+    #[should_panic(expected = "board row at line 4 has 4 numbers instead of 3")]
+    fn parse_board_with_rows_of_different_length() {
+        parse("1,2,3\n\n1 2 3\n4 5 6 7\n8 9\n");
+    }
 }
 
 mod part1 {
@@ -109,3 +115,112 @@ mod part2 {
         assert_eq!(score, 24742);
     }
 }
+
+mod finishing_order {
+    use super::*;
+
+    #[test]
+    fn finishing_order_example1() {
+        let game = parse(EXAMPLE1);
+
+        let order = game.finishing_order(WinningLines::RowsAndColumns);
+
+        assert_eq!(
+            order,
+            vec![
+                Win {
+                    board: 2,
+                    number: 24,
+                    turn: 12,
+                    score: 4512
+                },
+                Win {
+                    board: 0,
+                    number: 16,
+                    turn: 14,
+                    score: 2192
+                },
+                Win {
+                    board: 1,
+                    number: 13,
+                    turn: 15,
+                    score: 1924
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finishing_order_example1_with_diagonals() {
+        let game = parse(EXAMPLE1);
+
+        let order = game.finishing_order(WinningLines::WithDiagonals);
+
+        assert_eq!(
+            order[0],
+            Win {
+                board: 2,
+                number: 2,
+                turn: 8,
+                score: 494
+            }
+        );
+        assert_eq!(order.len(), 3);
+    }
+
+    #[test]
+    fn finishing_order_of_rectangular_boards() {
+        let game = parse(
+            "
+5,1,9,2,3,6,4
+
+1 2 3
+4 5 6
+
+9 8 7
+6 5 4
+",
+        );
+
+        let order = game.finishing_order(WinningLines::RowsAndColumns);
+
+        assert_eq!(game.boards[0].width(), 3);
+        assert_eq!(game.boards[0].height(), 2);
+        assert_eq!(
+            order,
+            vec![
+                Win {
+                    board: 0,
+                    number: 2,
+                    turn: 4,
+                    score: 26
+                },
+                Win {
+                    board: 1,
+                    number: 6,
+                    turn: 6,
+                    score: 114
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn boards_that_never_win_are_not_included() {
+        let game = Game {
+            drawn_numbers: vec![1, 5, 9],
+            boards: vec![Board::try_from(&[1u32, 2, 3, 4, 5, 6, 7, 8, 9][..]).unwrap()],
+        };
+
+        assert_eq!(game.finishing_order(WinningLines::RowsAndColumns), vec![]);
+        assert_eq!(
+            game.finishing_order(WinningLines::WithDiagonals),
+            vec![Win {
+                board: 0,
+                number: 9,
+                turn: 3,
+                score: 270
+            }]
+        );
+    }
+}
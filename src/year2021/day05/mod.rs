@@ -197,91 +197,308 @@ impl Iterator for LinePoints {
     }
 }
 
+/// The orientations of line segments supported by the analytic overlap
+/// counting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+    /// Rising in x and y at 45°
+    Diagonal,
+    /// Rising in x and falling in y at 45°
+    AntiDiagonal,
+}
+
+impl Orientation {
+    const ALL: [Orientation; 4] = [
+        Orientation::Horizontal,
+        Orientation::Vertical,
+        Orientation::Diagonal,
+        Orientation::AntiDiagonal,
+    ];
+
+    /// The coefficients `(a, b)` of the equation `a * x + b * y = key` that
+    /// all lines of this orientation satisfy.
+    fn coefficients(self) -> (i64, i64) {
+        match self {
+            Orientation::Horizontal => (0, 1),
+            Orientation::Vertical => (1, 0),
+            Orientation::Diagonal => (-1, 1),
+            Orientation::AntiDiagonal => (1, 1),
+        }
+    }
+
+    fn key(self, x: i64, y: i64) -> i64 {
+        let (a, b) = self.coefficients();
+        a * x + b * y
+    }
+
+    /// The position of a point along a line of this orientation.
+    fn position(self, x: i64, y: i64) -> i64 {
+        match self {
+            Orientation::Vertical => y,
+            _ => x,
+        }
+    }
+
+    fn point(self, key: i64, position: i64) -> (i64, i64) {
+        match self {
+            Orientation::Horizontal => (position, key),
+            Orientation::Vertical => (key, position),
+            Orientation::Diagonal => (position, key + position),
+            Orientation::AntiDiagonal => (position, key - position),
+        }
+    }
+}
+
+/// A line segment as a range of positions on one line of an orientation.
+/// Every position in the range is a point on the integer grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Span {
+    orientation: Orientation,
+    key: i64,
+    low: i64,
+    high: i64,
+}
+
+impl Span {
+    fn len(&self) -> u64 {
+        (self.high - self.low + 1) as u64
+    }
+
+    fn contains(&self, (x, y): (i64, i64)) -> bool {
+        let position = self.orientation.position(x, y);
+        self.orientation.key(x, y) == self.key && self.low <= position && position <= self.high
+    }
+
+    fn to_line_segment(self) -> LineSegment {
+        let (x1, y1) = self.orientation.point(self.key, self.low);
+        let (x2, y2) = self.orientation.point(self.key, self.high);
+        LineSegment {
+            start: Point {
+                x: x1 as i32,
+                y: y1 as i32,
+            },
+            end: Point {
+                x: x2 as i32,
+                y: y2 as i32,
+            },
+        }
+    }
+
+    fn intersection(&self, other: &Span) -> SpanIntersection {
+        if self.orientation == other.orientation {
+            if self.key != other.key {
+                return SpanIntersection::Empty;
+            }
+            let low = self.low.max(other.low);
+            let high = self.high.min(other.high);
+            return if low <= high {
+                SpanIntersection::Overlap(Span { low, high, ..*self })
+            } else {
+                SpanIntersection::Empty
+            };
+        }
+        let (a1, b1) = self.orientation.coefficients();
+        let (a2, b2) = other.orientation.coefficients();
+        let det = a1 * b2 - a2 * b1;
+        let x = self.key * b2 - other.key * b1;
+        let y = a1 * other.key - a2 * self.key;
+        if x % det != 0 || y % det != 0 {
+            // the lines cross between points of the grid
+            return SpanIntersection::Empty;
+        }
+        let point = (x / det, y / det);
+        if self.contains(point) && other.contains(point) {
+            SpanIntersection::Point(point)
+        } else {
+            SpanIntersection::Empty
+        }
+    }
+}
+
+enum SpanIntersection {
+    Empty,
+    Point((i64, i64)),
+    Overlap(Span),
+}
+
+impl LineSegment {
+    /// Returns the orientation or `None` if the segment is not horizontal,
+    /// vertical or diagonal at 45°. A single point counts as horizontal.
+    pub fn orientation(&self) -> Option<Orientation> {
+        let dx = self.end.x as i64 - self.start.x as i64;
+        let dy = self.end.y as i64 - self.start.y as i64;
+        if dy == 0 {
+            Some(Orientation::Horizontal)
+        } else if dx == 0 {
+            Some(Orientation::Vertical)
+        } else if dx == dy {
+            Some(Orientation::Diagonal)
+        } else if dx == -dy {
+            Some(Orientation::AntiDiagonal)
+        } else {
+            None
+        }
+    }
+
+    fn span(&self) -> Span {
+        let orientation = self
+            .orientation()
+            .expect("only horizontal, vertical and diagonal line segments are supported");
+        let (x1, y1) = (self.start.x as i64, self.start.y as i64);
+        let (x2, y2) = (self.end.x as i64, self.end.y as i64);
+        let position1 = orientation.position(x1, y1);
+        let position2 = orientation.position(x2, y2);
+        Span {
+            orientation,
+            key: orientation.key(x1, y1),
+            low: position1.min(position2),
+            high: position1.max(position2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intersection {
+    Empty,
+    Point(Point),
+    /// The line segments are collinear and overlap in this segment.
+    Overlap(LineSegment),
+}
+
 pub trait Intersects<Rhs = Self> {
-    fn intersects(&self, other: &Rhs) -> HashSet<Point>;
+    fn intersects(&self, other: &Rhs) -> Intersection;
 }
 
+/// Intersects line segments in points of the integer grid only. Diagonals that
+/// cross between grid points do not intersect.
+///
+/// # Panics
+///
+/// Panics if a line segment is not horizontal, vertical or diagonal at 45°.
 impl Intersects for LineSegment {
-    fn intersects(&self, other: &Self) -> HashSet<Point> {
-        let mut intersections = HashSet::new();
-        match (
-            self.is_horizontal(),
-            self.is_vertical(),
-            other.is_horizontal(),
-            other.is_vertical(),
-        ) {
-            (true, false, true, false) => {
-                if self.start.y == other.start.y {
-                    let y = self.start.y;
-                    let start = self
-                        .start
-                        .x
-                        .min(self.end.x)
-                        .max(other.start.x.min(other.end.x));
-                    let end = self
-                        .start
-                        .x
-                        .max(self.end.x)
-                        .min(other.start.x.max(other.end.x));
-                    intersections.extend((start..=end).map(|x| Point { x, y }));
-                }
-            }
-            (true, false, false, true) => {
-                let min_x = self.start.x.min(self.end.x);
-                let max_x = self.start.x.max(self.end.x);
-                let min_y = other.start.y.min(other.end.y);
-                let max_y = other.start.y.max(other.end.y);
-                if min_x <= other.start.x
-                    && other.start.x <= max_x
-                    && min_y <= self.start.y
-                    && self.start.y <= max_y
-                {
-                    intersections.insert(Point {
-                        x: other.start.x,
-                        y: self.start.y,
-                    });
-                }
-            }
-            (false, true, true, false) => {
-                let min_x = other.start.x.min(other.end.x);
-                let max_x = other.start.x.max(other.end.x);
-                let min_y = self.start.y.min(self.end.y);
-                let max_y = self.start.y.max(self.end.y);
-                if min_x <= self.start.x
-                    && self.start.x <= max_x
-                    && min_y <= other.start.y
-                    && other.start.y <= max_y
-                {
-                    intersections.insert(Point {
-                        x: self.start.x,
-                        y: other.start.y,
-                    });
+    fn intersects(&self, other: &Self) -> Intersection {
+        match self.span().intersection(&other.span()) {
+            SpanIntersection::Empty => Intersection::Empty,
+            SpanIntersection::Point((x, y)) => Intersection::Point(Point {
+                x: x as i32,
+                y: y as i32,
+            }),
+            SpanIntersection::Overlap(span) => Intersection::Overlap(span.to_line_segment()),
+        }
+    }
+}
+
+/// Merged overlapping spans grouped by the line they are on.
+#[derive(Debug, Default)]
+struct SpanUnion {
+    lines: HashMap<(Orientation, i64), Vec<(i64, i64)>>,
+}
+
+impl SpanUnion {
+    fn new(spans: impl IntoIterator<Item = Span>) -> Self {
+        let mut lines: HashMap<(Orientation, i64), Vec<(i64, i64)>> = HashMap::new();
+        for span in spans {
+            lines
+                .entry((span.orientation, span.key))
+                .or_default()
+                .push((span.low, span.high));
+        }
+        for ranges in lines.values_mut() {
+            ranges.sort_unstable();
+            let mut merged: Vec<(i64, i64)> = Vec::with_capacity(ranges.len());
+            for &(low, high) in ranges.iter() {
+                match merged.last_mut() {
+                    Some((_, last_high)) if low <= *last_high + 1 => {
+                        *last_high = (*last_high).max(high)
+                    },
+                    _ => merged.push((low, high)),
                 }
             }
-            (false, true, false, true) => {
-                if self.start.x == other.start.x {
-                    let x = self.start.x;
-                    let start = self
-                        .start
-                        .y
-                        .min(self.end.y)
-                        .max(other.start.y.min(other.end.y));
-                    let end = self
-                        .start
-                        .y
-                        .max(self.end.y)
-                        .min(other.start.y.max(other.end.y));
-                    intersections.extend((start..=end).map(|y| Point { x, y }));
-                }
+            *ranges = merged;
+        }
+        Self { lines }
+    }
+
+    fn spans(&self) -> impl Iterator<Item = Span> + '_ {
+        self.lines.iter().flat_map(|(&(orientation, key), ranges)| {
+            ranges.iter().map(move |&(low, high)| Span {
+                orientation,
+                key,
+                low,
+                high,
+            })
+        })
+    }
+
+    /// Counts the orientations of the spans that contain the point.
+    fn coverage(&self, (x, y): (i64, i64)) -> usize {
+        Orientation::ALL
+            .iter()
+            .filter(|orientation| {
+                let position = orientation.position(x, y);
+                self.lines
+                    .get(&(**orientation, orientation.key(x, y)))
+                    .is_some_and(|ranges| {
+                        let index = ranges.partition_point(|(low, _)| *low <= position);
+                        index > 0 && ranges[index - 1].1 >= position
+                    })
+            })
+            .count()
+    }
+}
+
+/// Counts the points of the integer grid where at least two line segments
+/// overlap.
+///
+/// Each pair of line segments is intersected analytically, so the run time
+/// does not depend on the length of the segments. Collinear overlaps are
+/// merged per line. Points where overlaps of different orientations cross
+/// are counted once.
+///
+/// # Panics
+///
+/// Panics if a line segment is not horizontal, vertical or diagonal at 45°.
+pub fn count_overlaps(line_segments: &[LineSegment]) -> u64 {
+    let spans = line_segments
+        .iter()
+        .map(LineSegment::span)
+        .collect::<Vec<_>>();
+    let mut overlaps = Vec::new();
+    let mut points = HashSet::new();
+    for (index, span1) in spans.iter().enumerate() {
+        for span2 in &spans[index + 1..] {
+            match span1.intersection(span2) {
+                SpanIntersection::Empty => {},
+                SpanIntersection::Point(point) => {
+                    points.insert(point);
+                },
+                SpanIntersection::Overlap(span) => overlaps.push(span),
             }
-            (true, true, _, _) => unreachable!(),
-            (_, _, true, true) => unreachable!(),
-            (_, _, _, _) => {
-                //ignore diagonal lines according too the puzzle definition
+        }
+    }
+
+    let union = SpanUnion::new(overlaps);
+    let merged = union.spans().collect::<Vec<_>>();
+    let mut crossings = HashSet::new();
+    for (index, span1) in merged.iter().enumerate() {
+        for span2 in &merged[index + 1..] {
+            if let SpanIntersection::Point(point) = span1.intersection(span2) {
+                crossings.insert(point);
             }
         }
-        intersections
     }
+    let counted_twice = crossings
+        .iter()
+        .map(|point| union.coverage(*point) as u64 - 1)
+        .sum::<u64>();
+    let in_overlaps = merged.iter().map(Span::len).sum::<u64>() - counted_twice;
+    let single_points = points
+        .iter()
+        .filter(|point| union.coverage(**point) == 0)
+        .count() as u64;
+    in_overlaps + single_points
 }
 
 #[aoc_generator(day5)]
@@ -301,30 +518,18 @@ pub fn parse(input: &str) -> Vec<LineSegment> {
 }
 
 #[aoc(day5, part1)]
-pub fn count_points_horizontal_and_vertical_lines_overlap(vent_lines: &[LineSegment]) -> usize {
-    let mut intersections = HashSet::new();
-    for (idx, line1) in vent_lines.iter().enumerate() {
-        for line2 in vent_lines.iter().skip(idx + 1) {
-            intersections.extend(line1.intersects(line2));
-        }
-    }
-    intersections.len()
-}
-
-fn point_map_of_line_segments(line_segments: &[LineSegment]) -> HashMap<Point, usize> {
-    let mut point_map = HashMap::new();
-    for line in line_segments {
-        for point in line.points() {
-            *point_map.entry(point).or_insert(0) += 1;
-        }
-    }
-    point_map
+pub fn count_points_horizontal_and_vertical_lines_overlap(vent_lines: &[LineSegment]) -> u64 {
+    let straight_lines = vent_lines
+        .iter()
+        .filter(|line| line.is_horizontal() || line.is_vertical())
+        .copied()
+        .collect::<Vec<_>>();
+    count_overlaps(&straight_lines)
 }
 
 #[aoc(day5, part2)]
-pub fn count_points_two_lines_overlap(vent_lines: &[LineSegment]) -> usize {
-    let point_map = point_map_of_line_segments(vent_lines);
-    point_map.values().filter(|count| **count >= 2).count()
+pub fn count_points_two_lines_overlap(vent_lines: &[LineSegment]) -> u64 {
+    count_overlaps(vent_lines)
 }
 
 #[cfg(test)]
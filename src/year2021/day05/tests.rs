@@ -58,3 +58,72 @@ mod part2 {
         assert_eq!(count, 21140);
     }
 }
+
+mod overlaps {
+    use super::*;
+
+    fn line(x1: i32, y1: i32, x2: i32, y2: i32) -> LineSegment {
+        LineSegment {
+            start: Point { x: x1, y: y1 },
+            end: Point { x: x2, y: y2 },
+        }
+    }
+
+    #[test]
+    fn diagonals_crossing_between_grid_points_do_not_intersect() {
+        let line1 = line(0, 0, 1, 1);
+        let line2 = line(0, 1, 1, 0);
+
+        assert_eq!(line1.intersects(&line2), Intersection::Empty);
+        assert_eq!(count_overlaps(&[line1, line2]), 0);
+    }
+
+    #[test]
+    fn collinear_diagonals_overlap_in_a_line_segment() {
+        let line1 = line(6, 6, 0, 0);
+        let line2 = line(3, 3, 9, 9);
+
+        assert_eq!(
+            line1.intersects(&line2),
+            Intersection::Overlap(line(3, 3, 6, 6))
+        );
+    }
+
+    #[test]
+    fn points_covered_by_many_line_segments_are_counted_once() {
+        let vent_lines = [
+            line(0, 0, 6, 6),
+            line(3, 3, 9, 9),
+            line(4, 4, 5, 5),
+            line(0, 8, 8, 0),
+            line(4, 0, 4, 9),
+            line(0, 4, 9, 4),
+        ];
+
+        assert_eq!(count_overlaps(&vent_lines), 4);
+    }
+
+    #[test]
+    fn crossing_overlaps_are_counted_once() {
+        let vent_lines = [
+            line(0, 5, 9, 5),
+            line(9, 5, 0, 5),
+            line(5, 0, 5, 9),
+            line(5, 9, 5, 0),
+        ];
+
+        assert_eq!(count_overlaps(&vent_lines), 19);
+    }
+
+    #[test]
+    fn huge_coordinates() {
+        let vent_lines = [
+            line(-1_000_000_000, 0, 1_000_000_000, 0),
+            line(1_000_000_000, 0, -1_000_000_000, 0),
+            line(0, -1_000_000_000, 0, 1_000_000_000),
+            line(-1_000_000_000, -1_000_000_000, 1_000_000_000, 1_000_000_000),
+        ];
+
+        assert_eq!(count_overlaps(&vent_lines), 2_000_000_001);
+    }
+}
@@ -86,6 +86,8 @@
 //!
 //! [Advent of Code 2021 - Day 7](https://adventofcode.com/2021/day/7)
 
+use std::ops::RangeInclusive;
+
 #[aoc_generator(day7)]
 pub fn parse(input: &str) -> Vec<i32> {
     input
@@ -98,41 +100,127 @@ pub fn parse(input: &str) -> Vec<i32> {
         .collect()
 }
 
-fn distance(a: i32, b: i32) -> i32 {
-    (a - b).abs()
+/// The position all crabs align to and the fuel they spend to get there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: i32,
+    pub fuel: i64,
 }
 
-#[aoc(day7, part1)]
-pub fn least_amount_of_fuel_linear(positions: &[i32]) -> i32 {
-    let min = *positions.iter().min().expect("no position given");
-    let max = *positions.iter().max().expect("no position given");
-    let mut min_fuel = i32::MAX;
-    for mid_point in min..=max {
-        let fuel = positions.iter().map(|pos| distance(*pos, mid_point)).sum();
-        if fuel < min_fuel {
-            min_fuel = fuel;
+/// A convex function of the distance a crab moves giving the fuel it spends.
+///
+/// Any closure `Fn(i64) -> i64` is a fuel cost. Its optimal alignment is
+/// found by ternary search between the outermost positions, which is only
+/// correct if the cost is convex.
+pub trait FuelCost {
+    fn fuel(&self, distance: i64) -> i64;
+
+    /// Returns the alignment needing the least fuel or `None` if there are no
+    /// positions. On ties the lowest position is chosen.
+    fn optimal_alignment(&self, positions: &[i32]) -> Option<Alignment> {
+        let mut low = *positions.iter().min()?;
+        let mut high = *positions.iter().max()?;
+        while high - low > 2 {
+            let third = (high - low) / 3;
+            let (mid1, mid2) = (low + third, high - third);
+            let fuel1 = total_fuel(self, positions, mid1);
+            let fuel2 = total_fuel(self, positions, mid2);
+            if fuel1 < fuel2 {
+                high = mid2 - 1;
+            } else if fuel1 > fuel2 {
+                low = mid1 + 1;
+            } else {
+                // by convexity no position above `mid2` needs less fuel
+                high = mid2;
+            }
         }
+        best_alignment(self, positions, low..=high)
     }
-    min_fuel
 }
 
-fn fuel(a: i32, b: i32) -> i32 {
-    let distance = distance(a, b);
-    distance * (distance + 1) / 2
+impl<F> FuelCost for F
+where
+    F: Fn(i64) -> i64,
+{
+    fn fuel(&self, distance: i64) -> i64 {
+        self(distance)
+    }
 }
 
-#[aoc(day7, part2)]
-pub fn least_amount_of_fuel_exponential(positions: &[i32]) -> i32 {
-    let min = *positions.iter().min().expect("no position given");
-    let max = *positions.iter().max().expect("no position given");
-    let mut min_fuel = i32::MAX;
-    for mid_point in min..=max {
-        let fuel = positions.iter().map(|pos| fuel(*pos, mid_point)).sum();
-        if fuel < min_fuel {
-            min_fuel = fuel;
+/// Every step costs one unit of fuel. The median is optimal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Linear;
+
+impl FuelCost for Linear {
+    fn fuel(&self, distance: i64) -> i64 {
+        distance
+    }
+
+    fn optimal_alignment(&self, positions: &[i32]) -> Option<Alignment> {
+        let mut sorted = positions.to_vec();
+        sorted.sort_unstable();
+        let median = *sorted.get((sorted.len().checked_sub(1)?) / 2)?;
+        best_alignment(self, positions, median..=median)
+    }
+}
+
+/// Each step costs one unit of fuel more than the previous step. The optimum
+/// is less than half a step away from the mean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Triangular;
+
+impl FuelCost for Triangular {
+    fn fuel(&self, distance: i64) -> i64 {
+        distance * (distance + 1) / 2
+    }
+
+    fn optimal_alignment(&self, positions: &[i32]) -> Option<Alignment> {
+        if positions.is_empty() {
+            return None;
         }
+        let sum = positions
+            .iter()
+            .map(|&position| position as i64)
+            .sum::<i64>();
+        let mean = sum.div_euclid(positions.len() as i64) as i32;
+        best_alignment(self, positions, mean..=mean + 1)
     }
-    min_fuel
+}
+
+fn total_fuel<C: FuelCost + ?Sized>(cost: &C, positions: &[i32], target: i32) -> i64 {
+    positions
+        .iter()
+        .map(|&position| cost.fuel((position as i64 - target as i64).abs()))
+        .sum()
+}
+
+fn best_alignment<C: FuelCost + ?Sized>(
+    cost: &C,
+    positions: &[i32],
+    candidates: RangeInclusive<i32>,
+) -> Option<Alignment> {
+    candidates
+        .map(|position| Alignment {
+            position,
+            fuel: total_fuel(cost, positions, position),
+        })
+        .min_by_key(|alignment| (alignment.fuel, alignment.position))
+}
+
+#[aoc(day7, part1)]
+pub fn least_amount_of_fuel_linear(positions: &[i32]) -> i64 {
+    Linear
+        .optimal_alignment(positions)
+        .expect("no position given")
+        .fuel
+}
+
+#[aoc(day7, part2)]
+pub fn least_amount_of_fuel_exponential(positions: &[i32]) -> i64 {
+    Triangular
+        .optimal_alignment(positions)
+        .expect("no position given")
+        .fuel
 }
 
 #[cfg(test)]
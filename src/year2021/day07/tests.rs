@@ -49,3 +49,84 @@ mod part2 {
         assert_eq!(fuel_consumption, 86397080);
     }
 }
+
+mod optimal_alignment {
+    use super::*;
+
+    #[test]
+    fn linear_optimal_alignment_example1() {
+        let positions = parse(EXAMPLE1);
+
+        let alignment = Linear.optimal_alignment(&positions);
+
+        assert_eq!(
+            alignment,
+            Some(Alignment {
+                position: 2,
+                fuel: 37
+            })
+        );
+    }
+
+    #[test]
+    fn triangular_optimal_alignment_example1() {
+        let positions = parse(EXAMPLE1);
+
+        let alignment = Triangular.optimal_alignment(&positions);
+
+        assert_eq!(
+            alignment,
+            Some(Alignment {
+                position: 5,
+                fuel: 168
+            })
+        );
+    }
+
+    #[test]
+    fn convex_optimal_alignment_example1() {
+        let positions = parse(EXAMPLE1);
+
+        let squared = |distance: i64| distance * distance;
+        let with_free_steps = |distance: i64| (distance - 5).max(0);
+
+        assert_eq!(
+            squared.optimal_alignment(&positions),
+            Some(Alignment {
+                position: 5,
+                fuel: 291
+            })
+        );
+        assert_eq!(
+            with_free_steps.optimal_alignment(&positions),
+            Some(Alignment {
+                position: 6,
+                fuel: 9
+            })
+        );
+    }
+
+    #[test]
+    fn convex_optimal_alignment_matches_closed_forms() {
+        let positions = parse(INPUT);
+
+        let linear = |distance: i64| Linear.fuel(distance);
+        let triangular = |distance: i64| Triangular.fuel(distance);
+
+        assert_eq!(
+            linear.optimal_alignment(&positions),
+            Linear.optimal_alignment(&positions)
+        );
+        assert_eq!(
+            triangular.optimal_alignment(&positions),
+            Triangular.optimal_alignment(&positions)
+        );
+    }
+
+    #[test]
+    fn no_positions() {
+        assert_eq!(Linear.optimal_alignment(&[]), None);
+        assert_eq!(Triangular.optimal_alignment(&[]), None);
+        assert_eq!((|distance: i64| distance).optimal_alignment(&[]), None);
+    }
+}
@@ -179,7 +179,6 @@
 //!
 //! [Advent of Code 2021 - Day 8](https://adventofcode.com/2021/day/8)
 
-use arrayvec::ArrayVec;
 use hashbrown::HashSet;
 use std::collections::HashMap;
 
@@ -314,88 +313,255 @@ pub fn count_digits_1_4_7_8(pattern_list: &[Pattern]) -> usize {
         .sum()
 }
 
-fn signal_segment_map(wires: impl IntoIterator<Item = char>) -> HashMap<char, usize> {
-    HashMap::from_iter(wires.into_iter().enumerate().map(|(i, c)| (c, i)))
-}
-
 fn digit_for_signal(
     signal: &HashSet<char>,
     signal_segment_map: &HashMap<char, usize>,
 ) -> Option<Digit> {
     let mut digit = [false; 7];
     for c in signal {
-        let i = *signal_segment_map.get(&c).unwrap();
+        let i = *signal_segment_map.get(c)?;
         digit[i] = true;
     }
     let digit = Digit::from(digit);
     DIGITS.iter().find(|valid| digit == **valid).copied()
 }
 
-struct Permutations<T, const N: usize> {
-    idxs: ArrayVec<T, N>,
-    swaps: ArrayVec<usize, N>,
-    i: usize,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The patterns are not the glyphs of the alphabet under any wiring.
+    Inconsistent,
+    /// The patterns fit the alphabet, but the listed wires can not be told
+    /// apart.
+    UnderDetermined(Vec<char>),
+}
+
+/// The glyphs of a segment display, like the digits of a seven-segment
+/// display. Each glyph is a bitmask of the segments it lights up, so a
+/// display can have up to 32 segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentAlphabet {
+    num_segments: usize,
+    glyphs: Vec<u32>,
+}
+
+/// Colors of segments and glyphs, or of wires and patterns, that tell apart
+/// elements as far as the structure of the display allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Coloring {
+    elements: Vec<u32>,
+    sets: Vec<u32>,
 }
 
-impl<T, const N: usize> From<ArrayVec<T, N>> for Permutations<T, N> {
-    fn from(array: ArrayVec<T, N>) -> Self {
+impl Coloring {
+    fn new(num_elements: usize, sets: &[u32]) -> Self {
         Self {
-            idxs: array,
-            swaps: ArrayVec::from([0; N]),
-            i: 0,
+            elements: vec![0; num_elements],
+            sets: sets.iter().map(|set| set.count_ones()).collect(),
         }
     }
-}
 
-impl<T, const N: usize> Iterator for Permutations<T, N>
-where
-    T: Clone,
-{
-    type Item = ArrayVec<T, N>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.i > 0 {
-            loop {
-                if self.i >= self.swaps.len() {
-                    return None;
-                }
-                if self.swaps[self.i] < self.i {
-                    break;
-                }
-                self.swaps[self.i] = 0;
-                self.i += 1;
-            }
-            self.idxs.swap(self.i, (self.i & 1) * self.swaps[self.i]);
-            self.swaps[self.i] += 1;
+    /// Colors each element by the colors of the sets it is in and each set by
+    /// the colors of its elements. Color ids are shared between colorings
+    /// refined with the same maps.
+    fn refine(
+        &self,
+        sets: &[u32],
+        element_ids: &mut HashMap<(u32, Vec<u32>), u32>,
+        set_ids: &mut HashMap<(u32, Vec<u32>), u32>,
+    ) -> Self {
+        fn intern(ids: &mut HashMap<(u32, Vec<u32>), u32>, key: (u32, Vec<u32>)) -> u32 {
+            let next = ids.len() as u32;
+            *ids.entry(key).or_insert(next)
         }
-        self.i = 1;
-        Some(self.idxs.clone())
+        let elements = (0..self.elements.len())
+            .map(|element| {
+                let mut signature = sets
+                    .iter()
+                    .zip(&self.sets)
+                    .filter(|(set, _)| *set & (1 << element) != 0)
+                    .map(|(_, color)| *color)
+                    .collect::<Vec<_>>();
+                signature.sort_unstable();
+                intern(element_ids, (self.elements[element], signature))
+            })
+            .collect();
+        let sets = sets
+            .iter()
+            .zip(&self.sets)
+            .map(|(set, color)| {
+                let mut signature = (0..self.elements.len())
+                    .filter(|element| set & (1 << element) != 0)
+                    .map(|element| self.elements[element])
+                    .collect::<Vec<_>>();
+                signature.sort_unstable();
+                intern(set_ids, (*color, signature))
+            })
+            .collect();
+        Self { elements, sets }
+    }
+
+    fn num_colors(&self) -> usize {
+        self.elements.iter().collect::<HashSet<_>>().len()
+            + self.sets.iter().collect::<HashSet<_>>().len()
+    }
+
+    fn histogram(&self) -> (Vec<u32>, Vec<u32>) {
+        let mut elements = self.elements.clone();
+        elements.sort_unstable();
+        let mut sets = self.sets.clone();
+        sets.sort_unstable();
+        (elements, sets)
     }
 }
 
-fn decode_signals(signals: &[HashSet<char>]) -> Option<HashMap<char, usize>> {
-    let mut found_mapping = None;
-    let one_signal = signals.iter().find(|signal| signal.len() == 2).unwrap();
-    let seven_signal = signals.iter().find(|signal| signal.len() == 3).unwrap();
-    let char0 = *seven_signal.difference(one_signal).next().unwrap();
-    let all_wires = ArrayVec::<_, 7>::from_iter(
-        ['a', 'b', 'c', 'd', 'e', 'f', 'g']
+impl SegmentAlphabet {
+    /// Creates an alphabet from the glyphs given as bitmasks of segments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 32 segments.
+    pub fn new(num_segments: usize, glyphs: Vec<u32>) -> Self {
+        assert!(num_segments <= 32, "at most 32 segments are supported");
+        Self {
+            num_segments,
+            glyphs,
+        }
+    }
+
+    /// The digits 0 to 9 of a seven-segment display, with segments numbered
+    /// in reading order.
+    pub fn seven_segment() -> Self {
+        let glyphs = DIGITS
+            .iter()
+            .map(|digit| {
+                digit
+                    .segments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, lit)| **lit)
+                    .fold(0, |mask, (segment, _)| mask | 1 << segment)
+            })
+            .collect();
+        Self::new(7, glyphs)
+    }
+
+    pub fn num_segments(&self) -> usize {
+        self.num_segments
+    }
+
+    pub fn glyphs(&self) -> &[u32] {
+        &self.glyphs
+    }
+
+    /// Derives which wire drives which segment from the patterns of all
+    /// glyphs of the alphabet in any order.
+    ///
+    /// Wires and segments are told apart by the sizes of the patterns they
+    /// are part of, then by the wires those patterns share, and so on, until
+    /// no more distinctions can be made. No wirings are tried out.
+    pub fn decode(&self, patterns: &[HashSet<char>]) -> Result<Wiring, DecodeError> {
+        let mut wires = patterns.iter().flatten().copied().collect::<Vec<_>>();
+        wires.sort_unstable();
+        wires.dedup();
+        if wires.len() != self.num_segments || patterns.len() != self.glyphs.len() {
+            return Err(DecodeError::Inconsistent);
+        }
+        let pattern_masks = patterns
             .iter()
-            .copied()
-            .filter(|c| *c != char0),
-    );
-    for mut wires in Permutations::from(all_wires) {
-        wires.insert(0, char0);
-        let mapping = signal_segment_map(wires);
-        if signals
+            .map(|pattern| {
+                pattern.iter().fold(0, |mask, wire| {
+                    mask | 1 << wires.binary_search(wire).unwrap_or_default()
+                })
+            })
+            .collect::<Vec<u32>>();
+
+        let mut observed = Coloring::new(wires.len(), &pattern_masks);
+        let mut expected = Coloring::new(self.num_segments, &self.glyphs);
+        loop {
+            if observed.histogram() != expected.histogram() {
+                return Err(DecodeError::Inconsistent);
+            }
+            let mut element_ids = HashMap::new();
+            let mut set_ids = HashMap::new();
+            let next_observed = observed.refine(&pattern_masks, &mut element_ids, &mut set_ids);
+            let next_expected = expected.refine(&self.glyphs, &mut element_ids, &mut set_ids);
+            if next_observed.num_colors() == observed.num_colors()
+                && next_expected.num_colors() == expected.num_colors()
+            {
+                break;
+            }
+            observed = next_observed;
+            expected = next_expected;
+        }
+
+        let mut ambiguous = Vec::new();
+        let mut segment_of_wire = HashMap::with_capacity(wires.len());
+        for (wire, color) in wires.iter().zip(&observed.elements) {
+            let mut segments = expected
+                .elements
+                .iter()
+                .enumerate()
+                .filter(|(_, segment_color)| *segment_color == color)
+                .map(|(segment, _)| segment);
+            match (segments.next(), segments.next()) {
+                (Some(segment), None) => {
+                    segment_of_wire.insert(*wire, segment);
+                },
+                _ => ambiguous.push(*wire),
+            }
+        }
+        if !ambiguous.is_empty() {
+            return Err(DecodeError::UnderDetermined(ambiguous));
+        }
+
+        let wiring = Wiring {
+            segment_of_wire,
+            glyphs: self.glyphs.clone(),
+        };
+        let mut decoded = patterns
             .iter()
-            .all(|signal| digit_for_signal(signal, &mapping).is_some())
-        {
-            found_mapping = Some(mapping);
-            break;
+            .map(|pattern| wiring.glyph(pattern))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(DecodeError::Inconsistent)?;
+        decoded.sort_unstable();
+        decoded.dedup();
+        if decoded.len() != self.glyphs.len() {
+            return Err(DecodeError::Inconsistent);
         }
+        Ok(wiring)
     }
-    found_mapping
+}
+
+/// Which wire drives which segment of a display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wiring {
+    segment_of_wire: HashMap<char, usize>,
+    glyphs: Vec<u32>,
+}
+
+impl Wiring {
+    pub fn segment(&self, wire: char) -> Option<usize> {
+        self.segment_of_wire.get(&wire).copied()
+    }
+
+    pub fn segment_of_wire(&self) -> &HashMap<char, usize> {
+        &self.segment_of_wire
+    }
+
+    /// Returns the index of the glyph shown by the signal.
+    pub fn glyph(&self, signal: &HashSet<char>) -> Option<usize> {
+        let mask = signal.iter().try_fold(0, |mask, wire| {
+            self.segment(*wire).map(|segment| mask | 1 << segment)
+        })?;
+        self.glyphs.iter().position(|glyph| *glyph == mask)
+    }
+}
+
+fn decode_signals(signals: &[HashSet<char>]) -> Option<HashMap<char, usize>> {
+    SegmentAlphabet::seven_segment()
+        .decode(signals)
+        .ok()
+        .map(|wiring| wiring.segment_of_wire)
 }
 
 #[aoc(day8, part2)]
@@ -63,6 +63,103 @@ mod part2 {
         );
     }
 
+    #[test]
+    fn decode_every_entry_example1() {
+        let alphabet = SegmentAlphabet::seven_segment();
+
+        for pattern in parse(EXAMPLE1) {
+            let wiring = alphabet.decode(&pattern.signals).unwrap();
+
+            assert_eq!(
+                pattern
+                    .signals
+                    .iter()
+                    .filter_map(|signal| wiring.glyph(signal))
+                    .collect::<HashSet<_>>()
+                    .len(),
+                10
+            );
+        }
+    }
+
+    #[test]
+    fn decode_inconsistent_signals() {
+        let alphabet = SegmentAlphabet::seven_segment();
+        let mut pattern = parse(EXAMPLE2)[0].clone();
+        pattern.signals[4] = HashSet::from_iter("abcd".chars());
+
+        let result = alphabet.decode(&pattern.signals);
+
+        assert_eq!(result, Err(DecodeError::Inconsistent));
+    }
+
+    #[test]
+    fn decode_duplicate_signals() {
+        let alphabet = SegmentAlphabet::seven_segment();
+        let mut pattern = parse(EXAMPLE2)[0].clone();
+        pattern.signals[0] = pattern.signals[1].clone();
+
+        let result = alphabet.decode(&pattern.signals);
+
+        assert_eq!(result, Err(DecodeError::Inconsistent));
+    }
+
+    #[test]
+    fn decode_under_determined_signals() {
+        let alphabet = SegmentAlphabet::new(4, vec![0b0001, 0b0010, 0b0111, 0b1111]);
+        let signals = ["x", "y", "xyz", "wxyz"]
+            .iter()
+            .map(|signal| HashSet::from_iter(signal.chars()))
+            .collect::<Vec<_>>();
+
+        let result = alphabet.decode(&signals);
+
+        assert_eq!(result, Err(DecodeError::UnderDetermined(vec!['x', 'y'])));
+    }
+
+    #[test]
+    fn decode_fourteen_segment_alphabet() {
+        let glyphs = vec![
+            0b00_0000_0011_1111,
+            0b00_1100_0000_0110,
+            0b00_0000_1101_1011,
+            0b00_0000_1000_1111,
+            0b00_0000_1110_0110,
+            0b10_0000_0110_1001,
+            0b00_0000_1111_1101,
+            0b01_0101_0000_0001,
+            0b00_0000_1111_1111,
+            0b00_0000_1110_1111,
+            0b00_0000_1111_0111,
+            0b01_0010_1000_1111,
+            0b00_0000_0011_1001,
+            0b01_0010_0000_1111,
+            0b00_0000_0111_1001,
+            0b00_0000_0111_0001,
+        ];
+        let alphabet = SegmentAlphabet::new(14, glyphs.clone());
+        let wires = "nkdgaimbjflhce".chars().collect::<Vec<_>>();
+        let signals = glyphs
+            .iter()
+            .rev()
+            .map(|glyph| {
+                wires
+                    .iter()
+                    .enumerate()
+                    .filter(|(segment, _)| glyph & (1 << segment) != 0)
+                    .map(|(_, wire)| *wire)
+                    .collect::<HashSet<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let wiring = alphabet.decode(&signals).unwrap();
+
+        for (segment, wire) in wires.iter().enumerate() {
+            assert_eq!(wiring.segment(*wire), Some(segment));
+        }
+        assert_eq!(wiring.glyph(&signals[0]), Some(15));
+    }
+
     #[test]
     fn sum_output_values_example2() {
         let pattern = parse(EXAMPLE2);
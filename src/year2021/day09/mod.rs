@@ -101,8 +101,6 @@
 //!
 //! [Advent of Code 2021 - Day 9](https://adventofcode.com/2021/day/9)
 
-use hashbrown::HashSet;
-
 #[aoc_generator(day9)]
 pub fn parse(input: &str) -> Vec<Vec<u32>> {
     input
@@ -174,51 +172,189 @@ pub fn sum_risk_level_at_low_points(heightmap: &[Vec<u32>]) -> u32 {
         .sum()
 }
 
-fn basin_at_low_point(low_point: Point, heightmap: &[Vec<u32>]) -> HashSet<Point> {
-    let y_max = heightmap.len() - 1;
-    let x_max = heightmap[0].len() - 1;
-    let mut basin = HashSet::new();
-    let mut to_fill = vec![low_point];
-    while let Some(point) = to_fill.pop() {
-        basin.insert(point);
-        let Point { x, y, h } = point;
-        if y > 0 {
-            let h1 = heightmap[y - 1][x];
-            if h1 > h && h1 != 9 {
-                to_fill.push(Point { x, y: y - 1, h: h1 });
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoundingBox {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+}
+
+impl BoundingBox {
+    fn at(x: usize, y: usize) -> Self {
+        Self {
+            min_x: x,
+            min_y: y,
+            max_x: x,
+            max_y: y,
         }
-        if y < y_max {
-            let h1 = heightmap[y + 1][x];
-            if h1 > h && h1 != 9 {
-                to_fill.push(Point { x, y: y + 1, h: h1 });
-            }
+    }
+
+    fn extend(&mut self, x: usize, y: usize) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Basin {
+    /// The lowest location of the basin, the first one in reading order if
+    /// there are several of the same height
+    pub low_point: Point,
+    pub size: usize,
+    pub bounds: BoundingBox,
+}
+
+#[derive(Debug, Clone)]
+struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
         }
-        if x > 0 {
-            let h1 = heightmap[y][x - 1];
-            if h1 > h && h1 != 9 {
-                to_fill.push(Point { x: x - 1, y, h: h1 });
+    }
+
+    fn find(&mut self, mut index: usize) -> usize {
+        while self.parent[index] != index {
+            self.parent[index] = self.parent[self.parent[index]];
+            index = self.parent[index];
+        }
+        index
+    }
+
+    fn union(&mut self, index1: usize, index2: usize) {
+        let (root1, root2) = (self.find(index1), self.find(index2));
+        if root1 == root2 {
+            return;
+        }
+        let (larger, smaller) = if self.size[root1] >= self.size[root2] {
+            (root1, root2)
+        } else {
+            (root2, root1)
+        };
+        self.parent[smaller] = larger;
+        self.size[larger] += self.size[smaller];
+    }
+}
+
+/// All basins of a heightmap and the basin each location belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasinMap {
+    width: usize,
+    labels: Vec<Option<usize>>,
+    basins: Vec<Basin>,
+}
+
+impl BasinMap {
+    /// Labels all locations of the heightmap with their basin.
+    ///
+    /// Locations of height 9 do not belong to any basin. Every other location
+    /// flows down to its lowest neighbor if it has a lower one, taking the
+    /// first one in reading order if several are equally low. A location
+    /// without a lower neighbor is part of a flat area and joins all neighbors
+    /// of the same height. So a plateau that flows down into several basins
+    /// connects them to a single basin.
+    pub fn label(heightmap: &[Vec<u32>]) -> Self {
+        let height = heightmap.len();
+        let width = heightmap.first().map_or(0, Vec::len);
+        let mut disjoint_set = DisjointSet::new(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let h = heightmap[y][x];
+                if h == 9 {
+                    continue;
+                }
+                let neighbors = [
+                    (y > 0).then(|| (x, y - 1)),
+                    (x > 0).then(|| (x - 1, y)),
+                    (x + 1 < width).then(|| (x + 1, y)),
+                    (y + 1 < height).then(|| (x, y + 1)),
+                ];
+                let lowest = neighbors
+                    .iter()
+                    .flatten()
+                    .filter(|(nx, ny)| heightmap[*ny][*nx] < h)
+                    .min_by_key(|(nx, ny)| heightmap[*ny][*nx]);
+                match lowest {
+                    Some((nx, ny)) => disjoint_set.union(y * width + x, ny * width + nx),
+                    None => neighbors
+                        .iter()
+                        .flatten()
+                        .filter(|(nx, ny)| heightmap[*ny][*nx] == h)
+                        .for_each(|(nx, ny)| disjoint_set.union(y * width + x, ny * width + nx)),
+                }
             }
         }
-        if x < x_max {
-            let h1 = heightmap[y][x + 1];
-            if h1 > h && h1 != 9 {
-                to_fill.push(Point { x: x + 1, y, h: h1 });
+
+        let mut labels = vec![None; width * height];
+        let mut label_of_root = vec![None; width * height];
+        let mut basins: Vec<Basin> = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let h = heightmap[y][x];
+                if h == 9 {
+                    continue;
+                }
+                let root = disjoint_set.find(y * width + x);
+                let label = *label_of_root[root].get_or_insert_with(|| {
+                    basins.push(Basin {
+                        low_point: Point { x, y, h },
+                        size: 0,
+                        bounds: BoundingBox::at(x, y),
+                    });
+                    basins.len() - 1
+                });
+                let basin = &mut basins[label];
+                basin.size += 1;
+                basin.bounds.extend(x, y);
+                if h < basin.low_point.h {
+                    basin.low_point = Point { x, y, h };
+                }
+                labels[y * width + x] = Some(label);
             }
         }
+        Self {
+            width,
+            labels,
+            basins,
+        }
+    }
+
+    /// Returns all basins in reading order of their topmost, leftmost
+    /// location.
+    pub fn basins(&self) -> &[Basin] {
+        &self.basins
+    }
+
+    pub fn basin_at(&self, x: usize, y: usize) -> Option<&Basin> {
+        if x >= self.width {
+            return None;
+        }
+        self.labels
+            .get(y * self.width + x)
+            .copied()
+            .flatten()
+            .map(|label| &self.basins[label])
     }
-    basin
 }
 
 #[aoc(day9, part2)]
 pub fn multiply_size_of_three_largest_basins(heightmap: &[Vec<u32>]) -> usize {
-    let low_points = low_points(heightmap);
-    let mut basins = low_points
+    let basin_map = BasinMap::label(heightmap);
+    let mut sizes = basin_map
+        .basins()
         .iter()
-        .map(|low_point| basin_at_low_point(*low_point, heightmap))
+        .map(|basin| basin.size)
         .collect::<Vec<_>>();
-    basins.sort_by(|basin1, basin2| basin2.len().cmp(&basin1.len()));
-    basins[0].len() * basins[1].len() * basins[2].len()
+    sizes.sort_unstable_by(|size1, size2| size2.cmp(size1));
+    sizes.iter().take(3).product()
 }
 
 #[cfg(test)]
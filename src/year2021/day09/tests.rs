@@ -32,6 +32,119 @@ mod part1 {
     }
 }
 
+mod basin_map {
+    use super::*;
+
+    #[test]
+    fn label_basins_example1() {
+        let heightmap = parse(EXAMPLE1);
+
+        let basin_map = BasinMap::label(&heightmap);
+
+        assert_eq!(
+            basin_map.basins(),
+            &[
+                Basin {
+                    low_point: Point { x: 1, y: 0, h: 1 },
+                    size: 3,
+                    bounds: BoundingBox {
+                        min_x: 0,
+                        min_y: 0,
+                        max_x: 1,
+                        max_y: 1
+                    }
+                },
+                Basin {
+                    low_point: Point { x: 9, y: 0, h: 0 },
+                    size: 9,
+                    bounds: BoundingBox {
+                        min_x: 5,
+                        min_y: 0,
+                        max_x: 9,
+                        max_y: 2
+                    }
+                },
+                Basin {
+                    low_point: Point { x: 2, y: 2, h: 5 },
+                    size: 14,
+                    bounds: BoundingBox {
+                        min_x: 0,
+                        min_y: 1,
+                        max_x: 5,
+                        max_y: 4
+                    }
+                },
+                Basin {
+                    low_point: Point { x: 6, y: 4, h: 5 },
+                    size: 9,
+                    bounds: BoundingBox {
+                        min_x: 5,
+                        min_y: 2,
+                        max_x: 9,
+                        max_y: 4
+                    }
+                },
+            ]
+        );
+        assert_eq!(
+            basin_map.basin_at(3, 3).map(|basin| basin.low_point),
+            Some(Point { x: 2, y: 2, h: 5 })
+        );
+        assert_eq!(basin_map.basin_at(2, 0), None);
+        assert_eq!(basin_map.basin_at(10, 0), None);
+    }
+
+    #[test]
+    fn plateau_without_way_down_is_one_basin() {
+        let heightmap = parse(
+            "
+33933
+33931
+99999
+",
+        );
+
+        let basin_map = BasinMap::label(&heightmap);
+
+        assert_eq!(basin_map.basins().len(), 2);
+        assert_eq!(basin_map.basins()[0].size, 4);
+        assert_eq!(basin_map.basins()[0].low_point, Point { x: 0, y: 0, h: 3 });
+        assert_eq!(basin_map.basins()[1].size, 4);
+        assert_eq!(basin_map.basins()[1].low_point, Point { x: 4, y: 1, h: 1 });
+    }
+
+    #[test]
+    fn basins_not_separated_by_9s_are_split_at_ridges() {
+        let heightmap = parse(
+            "
+1234321
+",
+        );
+
+        let basin_map = BasinMap::label(&heightmap);
+
+        assert_eq!(basin_map.basins().len(), 2);
+        assert_eq!(basin_map.basins()[0].size, 4);
+        assert_eq!(basin_map.basins()[1].size, 3);
+        assert_eq!(basin_map.basins()[1].low_point, Point { x: 6, y: 0, h: 1 });
+    }
+
+    #[test]
+    fn plateau_flowing_into_several_basins_joins_them() {
+        let heightmap = parse(
+            "
+1555552
+",
+        );
+
+        let basin_map = BasinMap::label(&heightmap);
+
+        assert_eq!(basin_map.basins().len(), 1);
+        assert_eq!(basin_map.basins()[0].size, 7);
+        assert_eq!(basin_map.basins()[0].low_point, Point { x: 0, y: 0, h: 1 });
+    }
+}
+
 mod part2 {
     use super::*;
 
@@ -158,113 +158,223 @@ pub enum SyntaxError {
     MissingClosingCharacter(Vec<char>),
 }
 
-fn matching_opening_character(closing: char) -> char {
-    match closing {
-        ')' => '(',
-        ']' => '[',
-        '}' => '{',
-        '>' => '<',
-        _ => panic!("invalid closing character"),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BracketPair {
+    pub opening: char,
+    pub closing: char,
+    /// Score of an unexpected occurrence of the closing character
+    pub error_score: u64,
+    /// Score of the closing character in a completion string
+    pub completion_score: u64,
+}
+
+/// A mismatch found in a line of code. Columns are counted in characters
+/// starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mismatch {
+    /// A closing character that does not close the innermost open chunk
+    Unexpected {
+        column: usize,
+        expected: char,
+        found: char,
+    },
+    /// A closing character while no chunk is open
+    Unopened { column: usize, found: char },
+}
+
+impl Mismatch {
+    pub fn column(&self) -> usize {
+        match *self {
+            Mismatch::Unexpected { column, .. } | Mismatch::Unopened { column, .. } => column,
+        }
     }
+
+    pub fn found(&self) -> char {
+        match *self {
+            Mismatch::Unexpected { found, .. } | Mismatch::Unopened { found, .. } => found,
+        }
+    }
+}
+
+/// The result of checking a line in recovery mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    pub mismatches: Vec<Mismatch>,
+    /// The closing characters needed to close all chunks still open at the
+    /// end of the line
+    pub completion: String,
 }
 
-fn matching_closing_character(opening: char) -> char {
-    match opening {
-        '(' => ')',
-        '[' => ']',
-        '{' => '}',
-        '<' => '>',
-        _ => panic!("invalid opening character"),
+/// Defines which characters open and close chunks and how errors and
+/// completions are scored.
+///
+/// Characters that are not part of any pair are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketGrammar {
+    pairs: Vec<BracketPair>,
+    completion_base: u64,
+}
+
+impl Default for BracketGrammar {
+    fn default() -> Self {
+        Self::new(5)
+            .with_pair('(', ')', 3, 1)
+            .with_pair('[', ']', 57, 2)
+            .with_pair('{', '}', 1197, 3)
+            .with_pair('<', '>', 25137, 4)
     }
 }
 
-fn parse_code_line(code_line: &str) -> Result<(), SyntaxError> {
-    let mut opened = Vec::new();
-    for c in code_line.chars() {
-        match c {
-            '(' | '[' | '{' | '<' => opened.push(c),
-            ')' | ']' | '}' | '>' => {
-                if let Some(opening) = opened.pop() {
-                    if c != matching_closing_character(opening) {
-                        return Err(SyntaxError::ClosingCharacterMismatch(opening, c));
-                    }
-                } else {
-                    return Err(SyntaxError::MissingOpeningCharacter(
-                        matching_opening_character(c),
-                    ));
+impl BracketGrammar {
+    /// Creates a grammar without any pairs. The score of a completion string
+    /// is calculated with the given base per character.
+    pub fn new(completion_base: u64) -> Self {
+        Self {
+            pairs: Vec::new(),
+            completion_base,
+        }
+    }
+
+    pub fn with_pair(
+        mut self,
+        opening: char,
+        closing: char,
+        error_score: u64,
+        completion_score: u64,
+    ) -> Self {
+        self.pairs.push(BracketPair {
+            opening,
+            closing,
+            error_score,
+            completion_score,
+        });
+        self
+    }
+
+    pub fn pairs(&self) -> &[BracketPair] {
+        &self.pairs
+    }
+
+    fn pair_opened_by(&self, opening: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.opening == opening)
+    }
+
+    fn pair_closed_by(&self, closing: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.closing == closing)
+    }
+
+    pub fn matching_closing_character(&self, opening: char) -> Option<char> {
+        self.pair_opened_by(opening).map(|pair| pair.closing)
+    }
+
+    pub fn matching_opening_character(&self, closing: char) -> Option<char> {
+        self.pair_closed_by(closing).map(|pair| pair.opening)
+    }
+
+    /// Checks the line up to the first error.
+    pub fn check(&self, code_line: &str) -> Result<(), SyntaxError> {
+        let mut opened: Vec<&BracketPair> = Vec::new();
+        for c in code_line.chars() {
+            if let Some(pair) = self.pair_opened_by(c) {
+                opened.push(pair);
+            } else if let Some(pair) = self.pair_closed_by(c) {
+                match opened.pop() {
+                    Some(open) if open.closing == c => {},
+                    Some(open) => {
+                        return Err(SyntaxError::ClosingCharacterMismatch(open.opening, c))
+                    },
+                    None => return Err(SyntaxError::MissingOpeningCharacter(pair.opening)),
                 }
             }
-            _ => {}
+        }
+        if opened.is_empty() {
+            Ok(())
+        } else {
+            let missing_closing = opened.iter().rev().map(|pair| pair.closing).collect();
+            Err(SyntaxError::MissingClosingCharacter(missing_closing))
         }
     }
-    if opened.is_empty() {
-        Ok(())
-    } else {
-        let missing_closing = opened
-            .into_iter()
-            .rev()
-            .map(matching_closing_character)
-            .collect();
-        Err(SyntaxError::MissingClosingCharacter(missing_closing))
+
+    /// Checks the whole line and reports every mismatch.
+    ///
+    /// After a mismatch the check recovers like this: if the unexpected
+    /// closing character closes a chunk further out, all chunks inside of it
+    /// are treated as closed. Otherwise the character is skipped.
+    pub fn recover(&self, code_line: &str) -> Recovery {
+        let mut mismatches = Vec::new();
+        let mut opened: Vec<&BracketPair> = Vec::new();
+        for (index, c) in code_line.chars().enumerate() {
+            let column = index + 1;
+            if let Some(pair) = self.pair_opened_by(c) {
+                opened.push(pair);
+            } else if self.pair_closed_by(c).is_some() {
+                match opened.last() {
+                    Some(open) if open.closing == c => {
+                        opened.pop();
+                    },
+                    Some(open) => {
+                        mismatches.push(Mismatch::Unexpected {
+                            column,
+                            expected: open.closing,
+                            found: c,
+                        });
+                        if let Some(outer) = opened.iter().rposition(|open| open.closing == c) {
+                            opened.truncate(outer);
+                        }
+                    },
+                    None => mismatches.push(Mismatch::Unopened { column, found: c }),
+                }
+            }
+        }
+        Recovery {
+            mismatches,
+            completion: opened.iter().rev().map(|pair| pair.closing).collect(),
+        }
     }
-}
 
-fn score_syntax_error(error: &SyntaxError) -> u64 {
-    match error {
-        SyntaxError::ClosingCharacterMismatch(_, c) => match c {
-            ')' => 3,
-            ']' => 57,
-            '}' => 1197,
-            '>' => 25137,
-            _ => 0,
-        },
-        SyntaxError::MissingOpeningCharacter(_) => 0,
-        SyntaxError::MissingClosingCharacter(_) => 0,
+    pub fn error_score(&self, error: &SyntaxError) -> u64 {
+        match error {
+            SyntaxError::ClosingCharacterMismatch(_, c) => {
+                self.pair_closed_by(*c).map_or(0, |pair| pair.error_score)
+            },
+            SyntaxError::MissingOpeningCharacter(_) => 0,
+            SyntaxError::MissingClosingCharacter(_) => 0,
+        }
     }
-}
 
-fn score_auto_complete(error: &SyntaxError) -> u64 {
-    match error {
-        SyntaxError::ClosingCharacterMismatch(_, _) => 0,
-        SyntaxError::MissingOpeningCharacter(_) => 0,
-        SyntaxError::MissingClosingCharacter(chars) => chars.iter().fold(0, |acc, c| {
-            acc * 5
-                + match c {
-                    ')' => 1,
-                    ']' => 2,
-                    '}' => 3,
-                    '>' => 4,
-                    _ => 0,
-                }
-        }),
+    pub fn completion_score(&self, completion: impl IntoIterator<Item = char>) -> u64 {
+        completion.into_iter().fold(0, |acc, c| {
+            acc * self.completion_base
+                + self
+                    .pair_closed_by(c)
+                    .map_or(0, |pair| pair.completion_score)
+        })
     }
 }
 
 #[aoc(day10, part1)]
 pub fn total_syntax_error_score(code_lines: &[String]) -> u64 {
+    let grammar = BracketGrammar::default();
     code_lines
         .iter()
-        .filter_map(|line| parse_code_line(line).err())
-        .filter(|err| match err {
-            SyntaxError::ClosingCharacterMismatch(_, _) => true,
-            _ => false,
-        })
-        .map(|err| score_syntax_error(&err))
+        .filter_map(|line| grammar.check(line).err())
+        .map(|err| grammar.error_score(&err))
         .sum()
 }
 
 #[aoc(day10, part2)]
 pub fn total_auto_complete_score(code_lines: &[String]) -> u64 {
+    let grammar = BracketGrammar::default();
     let mut scores = code_lines
         .iter()
-        .filter_map(|line| parse_code_line(line).err())
-        .filter(|err| match err {
-            SyntaxError::MissingClosingCharacter(_) => true,
-            _ => false,
+        .filter_map(|line| match grammar.check(line) {
+            Err(SyntaxError::MissingClosingCharacter(chars)) => {
+                Some(grammar.completion_score(chars))
+            },
+            _ => None,
         })
-        .map(|err| score_auto_complete(&err))
         .collect::<Vec<_>>();
-    scores.sort();
+    scores.sort_unstable();
     let middle = scores.len() / 2;
     scores[middle]
 }
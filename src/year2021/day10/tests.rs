@@ -15,6 +15,119 @@ const EXAMPLE1: &str = "
 <{([{{}}[<[[[<>{}]]]>[]]
 ";
 
+mod bracket_grammar {
+    use super::*;
+
+    #[test]
+    fn check_code_lines_example1() {
+        let grammar = BracketGrammar::default();
+        let code_lines = parse(EXAMPLE1);
+
+        assert_eq!(
+            grammar.check(&code_lines[2]),
+            Err(SyntaxError::ClosingCharacterMismatch('[', '}'))
+        );
+        assert_eq!(
+            grammar.check(&code_lines[0]),
+            Err(SyntaxError::MissingClosingCharacter(
+                "}}]])})]".chars().collect()
+            ))
+        );
+        assert_eq!(grammar.check("[<>({}){}[([])<>]]"), Ok(()));
+        assert_eq!(
+            grammar.check("()]"),
+            Err(SyntaxError::MissingOpeningCharacter('['))
+        );
+    }
+
+    #[test]
+    fn score_completion_example1() {
+        let grammar = BracketGrammar::default();
+
+        assert_eq!(grammar.completion_score("}}]])})]".chars()), 288957);
+        assert_eq!(grammar.completion_score("])}>".chars()), 294);
+    }
+
+    #[test]
+    fn recover_reports_every_mismatch_with_column() {
+        let grammar = BracketGrammar::default();
+
+        let recovery = grammar.recover("{([(<{}[<>[]}>{[]{[(<()>");
+
+        assert_eq!(
+            recovery,
+            Recovery {
+                mismatches: vec![
+                    Mismatch::Unexpected {
+                        column: 13,
+                        expected: ']',
+                        found: '}'
+                    },
+                    Mismatch::Unopened {
+                        column: 14,
+                        found: '>'
+                    },
+                ],
+                completion: ")]}}".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn recover_from_unopened_and_skipped_characters() {
+        let grammar = BracketGrammar::default();
+
+        let recovery = grammar.recover(")(]x)>");
+
+        assert_eq!(
+            recovery.mismatches,
+            vec![
+                Mismatch::Unopened {
+                    column: 1,
+                    found: ')'
+                },
+                Mismatch::Unexpected {
+                    column: 3,
+                    expected: ')',
+                    found: ']'
+                },
+                Mismatch::Unopened {
+                    column: 6,
+                    found: '>'
+                },
+            ]
+        );
+        assert_eq!(
+            recovery
+                .mismatches
+                .iter()
+                .map(Mismatch::column)
+                .collect::<Vec<_>>(),
+            vec![1, 3, 6]
+        );
+        assert_eq!(recovery.completion, "");
+    }
+
+    #[test]
+    fn user_defined_pairs_and_scores() {
+        let grammar = BracketGrammar::new(10)
+            .with_pair('a', 'z', 100, 1)
+            .with_pair('«', '»', 7, 2);
+
+        assert_eq!(grammar.matching_closing_character('«'), Some('»'));
+        assert_eq!(grammar.matching_opening_character(')'), None);
+        assert_eq!(grammar.check("a«»z()"), Ok(()));
+
+        let error = grammar.check("a«z»").unwrap_err();
+        assert_eq!(error, SyntaxError::ClosingCharacterMismatch('«', 'z'));
+        assert_eq!(grammar.error_score(&error), 100);
+
+        let recovery = grammar.recover("aa«");
+        assert_eq!(recovery.completion, "»zz");
+        assert_eq!(grammar.completion_score(recovery.completion.chars()), 211);
+    }
+}
+
 mod part1 {
     use super::*;
 
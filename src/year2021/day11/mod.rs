@@ -416,13 +416,18 @@ impl Add<(i32, i32)> for Position {
 pub struct Flashed;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct EnergyMap([HashSet<Position>; 10]);
+pub struct EnergyMap {
+    width: i32,
+    height: i32,
+    /// The positions of the octopuses for each energy level
+    levels: [HashSet<Position>; 10],
+}
 
 impl Display for EnergyMap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for y in 0..10 {
+        for y in 0..self.height {
             let mut line = String::new();
-            for x in 0..10 {
+            for x in 0..self.width {
                 line.push(
                     char::from_digit(u32::from(self.energy_level(Position { x, y }).unwrap()), 10)
                         .unwrap(),
@@ -438,19 +443,27 @@ impl EnergyMap {
     //! increases the energy level of each octopus and returns the positions
     //! of the octopuses that are flashing
     pub fn tick(&mut self) -> &HashSet<Position> {
-        self.0.rotate_right(1);
-        &self.0[0]
+        self.levels.rotate_right(1);
+        &self.levels[0]
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
     }
 
     pub fn increase_energy_level(&mut self, position: Position) -> Option<u8> {
         if let Some(mut level) = self.energy_level_inner(position) {
-            self.0[level].remove(&position);
+            self.levels[level].remove(&position);
             level += 1;
             if level == 10 {
-                self.0[0].insert(position);
+                self.levels[0].insert(position);
                 Some(0)
             } else {
-                self.0[level].insert(position);
+                self.levels[level].insert(position);
                 Some(level as u8)
             }
         } else {
@@ -459,13 +472,20 @@ impl EnergyMap {
     }
 
     fn energy_level_inner(&self, position: Position) -> Option<usize> {
-        self.0.iter().enumerate().find_map(|(level, positions)| {
-            if positions.contains(&position) {
-                Some(level)
-            } else {
-                None
-            }
-        })
+        if position.x < 0 || position.x >= self.width || position.y < 0 || position.y >= self.height
+        {
+            return None;
+        }
+        self.levels
+            .iter()
+            .enumerate()
+            .find_map(|(level, positions)| {
+                if positions.contains(&position) {
+                    Some(level)
+                } else {
+                    None
+                }
+            })
     }
 
     pub fn energy_level(&self, position: Position) -> Option<u8> {
@@ -473,78 +493,189 @@ impl EnergyMap {
     }
 
     pub fn is_all_flashing(&self) -> bool {
-        self.0.iter().skip(1).all(|positions| positions.is_empty())
+        self.levels
+            .iter()
+            .skip(1)
+            .all(|positions| positions.is_empty())
+    }
+
+    /// Performs one step and returns the octopuses that flashed in the order
+    /// of the rounds of the cascade. The octopuses of the first round flash
+    /// from their own energy, those of later rounds from the flashes of the
+    /// rounds before.
+    pub fn step(&mut self) -> Vec<Vec<Position>> {
+        let mut flashing_positions = self.tick().clone();
+        let mut rounds = Vec::new();
+        let mut round = Vec::from_iter(flashing_positions.iter().copied());
+        while !round.is_empty() {
+            let mut next_round = Vec::new();
+            for &position in &round {
+                for neighbor in NEIGHBORS.iter().copied().map(|offset| position + offset) {
+                    if flashing_positions.contains(&neighbor) {
+                        continue;
+                    }
+                    if let Some(0) = self.increase_energy_level(neighbor) {
+                        next_round.push(neighbor);
+                        flashing_positions.insert(neighbor);
+                    }
+                }
+            }
+            rounds.push(round);
+            round = next_round;
+        }
+        rounds
+    }
+
+    /// Returns the statistics of every step starting with step 1. The
+    /// iterator never ends.
+    pub fn step_stats(&self) -> impl Iterator<Item = StepStats> {
+        let num_octopuses = (self.width * self.height) as usize;
+        (1..).scan(self.clone(), move |energy_map, step| {
+            Some(StepStats::new(step, &energy_map.step(), num_octopuses))
+        })
+    }
+
+    /// Returns the first step in which all octopuses flash, looking at most
+    /// `max_steps` steps ahead.
+    pub fn first_synchronized_step(&self, max_steps: usize) -> Option<usize> {
+        self.step_stats()
+            .take(max_steps)
+            .find(|stats| stats.synchronized)
+            .map(|stats| stats.step)
+    }
+
+    /// Returns a frame for every step starting with step 1. The iterator
+    /// never ends.
+    pub fn frames(&self) -> Frames {
+        Frames {
+            energy_map: self.clone(),
+            step: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepStats {
+    pub step: usize,
+    pub flashes: usize,
+    /// The number of rounds of the cascade of flashes
+    pub cascade_depth: usize,
+    /// Whether all octopuses flashed, which requires at least one octopus
+    pub synchronized: bool,
+}
+
+impl StepStats {
+    fn new(step: usize, rounds: &[Vec<Position>], num_octopuses: usize) -> Self {
+        let flashes = rounds.iter().map(Vec::len).sum();
+        Self {
+            step,
+            flashes,
+            cascade_depth: rounds.len(),
+            synchronized: num_octopuses > 0 && flashes == num_octopuses,
+        }
+    }
+}
+
+/// The energy map after a step. Displays the octopuses that flashed with a
+/// letter for their round of the cascade, starting with `a`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub stats: StepStats,
+    pub energy_map: EnergyMap,
+    pub rounds: Vec<Vec<Position>>,
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = self
+            .energy_map
+            .to_string()
+            .lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for (round, positions) in self.rounds.iter().enumerate() {
+            let c = char::from(b'a' + round.min(25) as u8);
+            for position in positions {
+                lines[position.y as usize][position.x as usize] = c;
+            }
+        }
+        for line in lines {
+            writeln!(f, "{}", line.into_iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Frames {
+    energy_map: EnergyMap,
+    step: usize,
+}
+
+impl Iterator for Frames {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step += 1;
+        let rounds = self.energy_map.step();
+        let num_octopuses = (self.energy_map.width * self.energy_map.height) as usize;
+        Some(Frame {
+            stats: StepStats::new(self.step, &rounds, num_octopuses),
+            energy_map: self.energy_map.clone(),
+            rounds,
+        })
     }
 }
 
 #[aoc_generator(day11)]
 pub fn parse(input: &str) -> EnergyMap {
-    let mut energy_map = [
-        HashSet::new(),
-        HashSet::new(),
-        HashSet::new(),
-        HashSet::new(),
-        HashSet::new(),
-        HashSet::new(),
-        HashSet::new(),
-        HashSet::new(),
-        HashSet::new(),
-        HashSet::new(),
-    ];
+    let mut levels: [HashSet<Position>; 10] = Default::default();
+    let mut width = 0;
+    let mut height = 0;
     for (y, line) in input.lines().filter(|line| !line.is_empty()).enumerate() {
+        let mut line_width = 0;
         for (x, c) in line.chars().enumerate() {
             let level = c
                 .to_digit(10)
                 .unwrap_or_else(|| panic!("invalid digit {}", c)) as usize;
-            energy_map[level].insert(Position {
+            levels[level].insert(Position {
                 x: x as i32,
                 y: y as i32,
             });
+            line_width += 1;
         }
-    }
-    EnergyMap(energy_map)
-}
-
-fn step(energy_map: &mut EnergyMap) -> HashSet<Position> {
-    let mut flashing_positions = energy_map.tick().clone();
-    let mut to_check = Vec::from_iter(flashing_positions.iter().copied());
-    while let Some(position) = to_check.pop() {
-        for neighbor in NEIGHBORS.iter().copied().map(|offset| position + offset) {
-            if flashing_positions.contains(&neighbor) {
-                continue;
-            }
-            if let Some(level) = energy_map.increase_energy_level(neighbor) {
-                if level == 0 {
-                    to_check.push(neighbor);
-                    flashing_positions.insert(neighbor);
-                }
-            }
+        if y == 0 {
+            width = line_width;
+        } else if line_width != width {
+            panic!(
+                "line {} has {} octopuses instead of {}",
+                y + 1,
+                line_width,
+                width
+            );
         }
+        height += 1;
+    }
+    EnergyMap {
+        width,
+        height,
+        levels,
     }
-    //eprintln!("{}", &energy_map);
-    flashing_positions
 }
 
 #[aoc(day11, part1)]
 pub fn solve_part1(energy_map: &EnergyMap) -> usize {
-    //eprintln!("{}", energy_map);
-    let mut energy_map = energy_map.clone();
-    (0..100).fold(0, |count, _| count + step(&mut energy_map).len())
+    energy_map
+        .step_stats()
+        .take(100)
+        .map(|stats| stats.flashes)
+        .sum()
 }
 
 #[aoc(day11, part2)]
 pub fn solve_part2(energy_map: &EnergyMap) -> usize {
-    //eprintln!("{}", energy_map);
-    let mut energy_map = energy_map.clone();
-    (1..)
-        .find_map(|num| {
-            step(&mut energy_map);
-            if energy_map.is_all_flashing() {
-                Some(num)
-            } else {
-                None
-            }
-        })
+    energy_map
+        .first_synchronized_step(usize::MAX)
         .expect("no all flashing moment")
 }
 
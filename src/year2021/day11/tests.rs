@@ -58,3 +58,95 @@ mod part2 {
         assert_eq!(result, 437);
     }
 }
+
+const EXAMPLE2: &str = "
+11111
+19991
+19191
+19991
+11111
+";
+
+mod step_stats {
+    use super::*;
+
+    #[test]
+    fn step_stats_example1() {
+        let energy_map = parse(EXAMPLE1);
+
+        let stats = energy_map.step_stats().take(2).collect::<Vec<_>>();
+
+        assert_eq!(
+            stats,
+            vec![
+                StepStats {
+                    step: 1,
+                    flashes: 0,
+                    cascade_depth: 0,
+                    synchronized: false
+                },
+                StepStats {
+                    step: 2,
+                    flashes: 35,
+                    cascade_depth: 5,
+                    synchronized: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn first_synchronized_step_example1() {
+        let energy_map = parse(EXAMPLE1);
+
+        assert_eq!(energy_map.first_synchronized_step(194), None);
+        assert_eq!(energy_map.first_synchronized_step(195), Some(195));
+    }
+
+    #[test]
+    fn empty_grid_never_synchronizes() {
+        let energy_map = parse("");
+
+        assert_eq!(energy_map.first_synchronized_step(10), None);
+    }
+
+    #[test]
+    fn rectangular_grid() {
+        let energy_map = parse("989\n898\n");
+
+        let stats = energy_map.step_stats().next();
+
+        assert_eq!(energy_map.width(), 3);
+        assert_eq!(energy_map.height(), 2);
+        assert_eq!(
+            stats,
+            Some(StepStats {
+                step: 1,
+                flashes: 6,
+                cascade_depth: 2,
+                synchronized: true
+            })
+        );
+    }
+}
+
+mod frames {
+    use super::*;
+
+    #[test]
+    fn frames_example2() {
+        let energy_map = parse(EXAMPLE2);
+
+        let frames = energy_map.frames().take(2).collect::<Vec<_>>();
+
+        assert_eq!(frames[0].stats.flashes, 9);
+        assert_eq!(frames[0].stats.cascade_depth, 2);
+        assert_eq!(frames[0].to_string(), "34543\n4aaa4\n5aba5\n4aaa4\n34543\n");
+        assert_eq!(
+            frames[0].energy_map.to_string(),
+            "34543\n40004\n50005\n40004\n34543\n"
+        );
+        assert_eq!(frames[1].stats.flashes, 0);
+        assert_eq!(frames[1].to_string(), "45654\n51115\n61116\n51115\n45654\n");
+    }
+}
@@ -273,70 +273,218 @@ impl FromIterator<Connection> for CaveMap {
 }
 
 impl CaveMap {
-    pub fn find_all_paths(&self, start: &str, end: &str) -> Option<Vec<Vec<Cave>>> {
+    fn is_small(&self, index: usize) -> bool {
+        self.caves[&index].kind == CaveKind::Small
+    }
+
+    /// Paths could go back and forth between two connected big caves forever.
+    fn assert_no_connected_big_caves(&self) {
+        let connected = self
+            .adjacent_list
+            .iter()
+            .enumerate()
+            .any(|(index, adjacent)| {
+                !self.is_small(index) && adjacent.iter().any(|neighbor| !self.is_small(*neighbor))
+            });
+        assert!(!connected, "big caves must not be connected to each other");
+    }
+
+    /// Counts the paths from start to end without listing them.
+    ///
+    /// Big caves can be visited any number of times, small caves once. The
+    /// budget allows that many additional visits of small caves in total.
+    /// The start cave is never entered again and a path stops at the end cave.
+    ///
+    /// Returns `None` if the start or end cave is not on the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two big caves are connected or if there are more than 64
+    /// small caves.
+    pub fn count_paths(&self, start: &str, end: &str, small_revisit_budget: u32) -> Option<u64> {
         let start_index = *self.indexes.get(start)?;
         let end_index = *self.indexes.get(end)?;
-        let mut found_paths = Vec::new();
-        let mut to_visit = Vec::new();
-        to_visit.push((start_index, vec![start_index], HashSet::new()));
-        while let Some((current, path, once)) = to_visit.pop() {
-            for &neighbor in &self.adjacent_list[current] {
-                if neighbor == end_index {
-                    let mut found = path.clone();
-                    found.push(end_index);
-                    found_paths.push(found);
-                } else if neighbor == start_index {
-                } else {
-                    let mut once = once.clone();
-                    if self.caves[&neighbor].kind == CaveKind::Big || once.insert(neighbor) {
-                        let mut path = path.clone();
-                        path.push(neighbor);
-                        to_visit.push((neighbor, path, once));
-                    }
-                }
+        self.assert_no_connected_big_caves();
+        let mut bits = vec![None; self.adjacent_list.len()];
+        let mut num_small = 0;
+        for (index, bit) in bits.iter_mut().enumerate() {
+            if self.is_small(index) {
+                *bit = Some(num_small);
+                num_small += 1;
             }
         }
-        Some(
-            found_paths
-                .iter()
-                .map(|path| path.iter().map(|i| self.caves[i].clone()).collect())
-                .collect(),
-        )
+        assert!(num_small <= 64, "at most 64 small caves are supported");
+        let mut counter = PathCounter {
+            cave_map: self,
+            start: start_index,
+            end: end_index,
+            bits,
+            memo: HashMap::new(),
+        };
+        let visited = counter.bits[start_index].map_or(0, |bit| 1 << bit);
+        Some(counter.count_from(start_index, visited, small_revisit_budget))
     }
 
-    pub fn find_all_paths2(&self, start: &str, end: &str) -> Option<Vec<Vec<Cave>>> {
+    /// Returns an iterator that finds the paths from start to end one by one,
+    /// following the same rules as `count_paths`.
+    ///
+    /// Returns `None` if the start or end cave is not on the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two big caves are connected.
+    pub fn paths(&self, start: &str, end: &str, small_revisit_budget: u32) -> Option<Paths<'_>> {
         let start_index = *self.indexes.get(start)?;
         let end_index = *self.indexes.get(end)?;
-        let mut found_paths = Vec::new();
-        let mut to_visit = Vec::new();
-        to_visit.push((start_index, vec![start_index], HashSet::new(), 0));
-        while let Some((current, path, once, dup_small)) = to_visit.pop() {
-            for &neighbor in &self.adjacent_list[current] {
-                if neighbor == end_index {
-                    let mut found = path.clone();
-                    found.push(end_index);
-                    found_paths.push(found);
-                } else if neighbor == start_index {
-                } else {
-                    let mut dup_small = dup_small;
-                    let mut once = once.clone();
-                    if self.caves[&neighbor].kind == CaveKind::Big || once.insert(neighbor) || {
-                        dup_small += 1;
-                        dup_small <= 1
-                    } {
-                        let mut path = path.clone();
-                        path.push(neighbor);
-                        to_visit.push((neighbor, path, once, dup_small));
+        self.assert_no_connected_big_caves();
+        let neighbors = self
+            .adjacent_list
+            .iter()
+            .map(|adjacent| {
+                let mut neighbors = adjacent.iter().copied().collect::<Vec<_>>();
+                neighbors.sort_unstable();
+                neighbors
+            })
+            .collect();
+        Some(Paths {
+            cave_map: self,
+            start: start_index,
+            end: end_index,
+            neighbors,
+            visits: vec![0; self.adjacent_list.len()],
+            budget: small_revisit_budget,
+            stack: vec![(start_index, 0)],
+        })
+    }
+}
+
+struct PathCounter<'a> {
+    cave_map: &'a CaveMap,
+    start: usize,
+    end: usize,
+    bits: Vec<Option<u32>>,
+    memo: HashMap<(usize, u64, u64, u32), u64>,
+}
+
+impl PathCounter<'_> {
+    /// Returns the small caves that can still be reached from the current
+    /// cave. Without any revisits left, visited small caves block the way.
+    ///
+    /// The number of paths only depends on this part of the map, so paths
+    /// that differ in the caves visited behind them share their count.
+    fn reachable(&self, current: usize, visited: u64, budget: u32) -> u64 {
+        let mut reachable = 0;
+        let mut seen = vec![false; self.bits.len()];
+        seen[current] = true;
+        let mut to_visit = vec![current];
+        while let Some(cave) = to_visit.pop() {
+            for &neighbor in &self.cave_map.adjacent_list[cave] {
+                if seen[neighbor] || neighbor == self.start {
+                    continue;
+                }
+                seen[neighbor] = true;
+                if let Some(bit) = self.bits[neighbor] {
+                    if budget == 0 && visited & 1 << bit != 0 {
+                        continue;
+                    }
+                    reachable |= 1 << bit;
+                }
+                if neighbor != self.end {
+                    to_visit.push(neighbor);
+                }
+            }
+        }
+        reachable
+    }
+
+    fn count_from(&mut self, current: usize, visited: u64, budget: u32) -> u64 {
+        let reachable = self.reachable(current, visited, budget);
+        let key = (current, reachable, visited & reachable, budget);
+        if let Some(count) = self.memo.get(&key) {
+            return *count;
+        }
+        let mut count = 0;
+        for &neighbor in &self.cave_map.adjacent_list[current] {
+            if neighbor == self.end {
+                count += 1;
+            } else if neighbor == self.start {
+            } else {
+                match self.bits[neighbor] {
+                    None => count += self.count_from(neighbor, visited, budget),
+                    Some(bit) if visited & 1 << bit == 0 => {
+                        count += self.count_from(neighbor, visited | 1 << bit, budget)
+                    },
+                    Some(_) if budget > 0 => {
+                        count += self.count_from(neighbor, visited, budget - 1)
+                    },
+                    Some(_) => {},
+                }
+            }
+        }
+        self.memo.insert(key, count);
+        count
+    }
+}
+
+/// Iterator over the paths through a cave system in depth first order.
+#[derive(Debug)]
+pub struct Paths<'a> {
+    cave_map: &'a CaveMap,
+    start: usize,
+    end: usize,
+    neighbors: Vec<Vec<usize>>,
+    visits: Vec<u32>,
+    budget: u32,
+    /// The caves of the current path, each with the position of the next
+    /// neighbor to try
+    stack: Vec<(usize, usize)>,
+}
+
+impl<'a> Iterator for Paths<'a> {
+    type Item = Vec<&'a Cave>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((current, next_neighbor)) = self.stack.last_mut() {
+            let current = *current;
+            let neighbor = match self.neighbors[current].get(*next_neighbor) {
+                Some(neighbor) => *neighbor,
+                None => {
+                    self.stack.pop();
+                    if current != self.start && self.cave_map.is_small(current) {
+                        self.visits[current] -= 1;
+                        if self.visits[current] > 0 {
+                            self.budget += 1;
+                        }
+                    }
+                    continue;
+                },
+            };
+            *next_neighbor += 1;
+            if neighbor == self.start {
+                continue;
+            }
+            if neighbor == self.end {
+                return Some(
+                    self.stack
+                        .iter()
+                        .map(|(index, _)| index)
+                        .chain([&self.end])
+                        .map(|index| &self.cave_map.caves[index])
+                        .collect(),
+                );
+            }
+            if self.cave_map.is_small(neighbor) {
+                if self.visits[neighbor] > 0 {
+                    if self.budget == 0 {
+                        continue;
                     }
+                    self.budget -= 1;
                 }
+                self.visits[neighbor] += 1;
             }
+            self.stack.push((neighbor, 0));
         }
-        Some(
-            found_paths
-                .iter()
-                .map(|path| path.iter().map(|i| self.caves[i].clone()).collect())
-                .collect(),
-        )
+        None
     }
 }
 
@@ -353,21 +501,19 @@ pub fn parse(input: &str) -> Vec<Connection> {
 }
 
 #[aoc(day12, part1)]
-pub fn solve_part1(input: &[Connection]) -> usize {
+pub fn solve_part1(input: &[Connection]) -> u64 {
     let cave_map = CaveMap::from_iter(input.iter().cloned());
     cave_map
-        .find_all_paths("start", "end")
+        .count_paths("start", "end", 0)
         .expect("no path found at all")
-        .len()
 }
 
 #[aoc(day12, part2)]
-pub fn solve_part2(input: &[Connection]) -> usize {
+pub fn solve_part2(input: &[Connection]) -> u64 {
     let cave_map = CaveMap::from_iter(input.iter().cloned());
     cave_map
-        .find_all_paths2("start", "end")
+        .count_paths("start", "end", 1)
         .expect("no path found at all")
-        .len()
 }
 
 #[cfg(test)]
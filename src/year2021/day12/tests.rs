@@ -46,6 +46,87 @@ pj-fs
 start-RW
 ";
 
+mod cave_map {
+    use super::*;
+
+    fn cave_map(input: &str) -> CaveMap {
+        CaveMap::from_iter(parse(input))
+    }
+
+    fn names(path: Vec<&Cave>) -> String {
+        path.iter()
+            .map(|cave| cave.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    #[test]
+    fn paths_example1() {
+        let cave_map = cave_map(EXAMPLE1);
+
+        let mut paths = cave_map
+            .paths("start", "end", 0)
+            .unwrap()
+            .map(names)
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            vec![
+                "start,A,b,A,c,A,end",
+                "start,A,b,A,end",
+                "start,A,b,end",
+                "start,A,c,A,b,A,end",
+                "start,A,c,A,b,end",
+                "start,A,c,A,end",
+                "start,A,end",
+                "start,b,A,c,A,end",
+                "start,b,A,end",
+                "start,b,end",
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_agree_with_count_paths() {
+        for input in [EXAMPLE1, EXAMPLE2, EXAMPLE3] {
+            let cave_map = cave_map(input);
+            for budget in 0..=2 {
+                let count = cave_map.count_paths("start", "end", budget).unwrap();
+                let paths = cave_map.paths("start", "end", budget).unwrap();
+
+                assert_eq!(paths.count() as u64, count);
+            }
+        }
+    }
+
+    #[test]
+    fn count_paths_between_any_caves() {
+        let cave_map = cave_map(EXAMPLE1);
+
+        assert_eq!(cave_map.count_paths("c", "d", 0), Some(9));
+        assert_eq!(cave_map.paths("c", "d", 0).unwrap().count(), 9);
+        assert_eq!(cave_map.count_paths("start", "x", 0), None);
+    }
+
+    #[test]
+    fn count_paths_with_dozens_of_small_caves() {
+        let mut input = String::new();
+        let mut previous = "start".to_string();
+        for i in 0..20 {
+            let joint = format!("c{}", i);
+            input.push_str(&format!("{}-a{}\n{}-b{}\n", previous, i, previous, i));
+            input.push_str(&format!("a{}-{}\nb{}-{}\n", i, joint, i, joint));
+            previous = joint;
+        }
+        input.push_str(&format!("{}-end\n", previous));
+        let cave_map = cave_map(&input);
+
+        assert_eq!(cave_map.count_paths("start", "end", 0), Some(1 << 20));
+    }
+}
+
 mod part1 {
     use super::*;
 
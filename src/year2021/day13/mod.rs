@@ -160,6 +160,7 @@
 use hashbrown::HashSet;
 use std::fmt;
 use std::fmt::{Display, Write};
+use std::io;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -185,6 +186,8 @@ impl FromStr for Point {
 pub enum Fold {
     Left(i32),
     Up(i32),
+    /// Folds the part right of the line `x=y` onto the part below it.
+    Diagonal,
 }
 
 impl FromStr for Fold {
@@ -194,6 +197,9 @@ impl FromStr for Fold {
         let (_, tail) = s
             .split_once("fold along ")
             .ok_or_else(|| format!("invalid fold entry: {}", s))?;
+        if tail == "x=y" {
+            return Ok(Fold::Diagonal);
+        }
         let (left, right) = tail
             .split_once('=')
             .ok_or_else(|| format!("invalid fold entry: {}", tail))?;
@@ -217,18 +223,11 @@ impl FromIterator<Point> for Image {
 
 impl Display for Image {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut min_x = i32::MAX;
-        let mut max_x = i32::MIN;
-        let mut min_y = i32::MAX;
-        let mut max_y = i32::MIN;
-        for p in self.0.iter() {
-            min_x = min_x.min(p.x);
-            max_x = max_x.max(p.x);
-            min_y = min_y.min(p.y);
-            max_y = max_y.max(p.y);
-        }
-        for y in min_y..=max_y {
-            for x in min_x..=max_x {
+        let Some((min, max)) = self.bounds() else {
+            return Ok(());
+        };
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
                 if self.0.get(&Point { x, y }).is_some() {
                     f.write_char('#')?;
                 } else {
@@ -242,8 +241,12 @@ impl Display for Image {
 }
 
 impl Image {
+    /// Folds the image along the fold line. The fold line does not need to
+    /// be in the middle. If the folded part is larger than the other part,
+    /// dots mirrored past zero are moved back so that no coordinate is
+    /// negative.
     pub fn fold(&self, fold: Fold) -> Self {
-        Self(match fold {
+        let folded = Self(match fold {
             Fold::Left(fx) => self
                 .0
                 .iter()
@@ -262,12 +265,121 @@ impl Image {
                     Point { x: p.x, y }
                 })
                 .collect(),
+            Fold::Diagonal => self
+                .0
+                .iter()
+                .map(|p| {
+                    if p.x > p.y {
+                        Point { x: p.y, y: p.x }
+                    } else {
+                        *p
+                    }
+                })
+                .collect(),
+        });
+        folded.normalized()
+    }
+
+    /// Moves the dots so that no coordinate is negative.
+    fn normalized(self) -> Self {
+        match self.bounds() {
+            Some((min, _)) if min.x < 0 || min.y < 0 => {
+                let (dx, dy) = (min.x.min(0), min.y.min(0));
+                self.0
+                    .into_iter()
+                    .map(|p| Point {
+                        x: p.x - dx,
+                        y: p.y - dy,
+                    })
+                    .collect()
+            },
+            _ => self,
+        }
+    }
+
+    /// Returns the top left and bottom right corner of the smallest rectangle
+    /// that contains all dots or `None` if there are no dots.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        self.0.iter().fold(None, |bounds, p| {
+            let (min, max) = bounds.unwrap_or((*p, *p));
+            Some((
+                Point {
+                    x: min.x.min(p.x),
+                    y: min.y.min(p.y),
+                },
+                Point {
+                    x: max.x.max(p.x),
+                    y: max.y.max(p.y),
+                },
+            ))
         })
     }
 
     pub fn dots(&self) -> impl Iterator<Item = Point> + '_ {
         self.0.iter().copied()
     }
+
+    /// Returns the bounds of the image as top left corner, width and height.
+    fn frame(&self) -> (Point, usize, usize) {
+        match self.bounds() {
+            Some((min, max)) => (
+                min,
+                (max.x - min.x) as usize + 1,
+                (max.y - min.y) as usize + 1,
+            ),
+            None => (Point { x: 0, y: 0 }, 0, 0),
+        }
+    }
+
+    /// Writes the image as binary portable bitmap (PBM) with dots in black.
+    pub fn write_pbm(&self, out: &mut impl io::Write) -> io::Result<()> {
+        let (origin, width, height) = self.frame();
+        write!(out, "P4\n{} {}\n", width, height)?;
+        let mut row = vec![0u8; width.div_ceil(8)];
+        for y in 0..height {
+            row.fill(0);
+            for x in 0..width {
+                if self.contains(origin, x, y) {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            out.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the image as binary portable pixmap (PPM) with the given RGB
+    /// colors for dots and background.
+    pub fn write_ppm(
+        &self,
+        out: &mut impl io::Write,
+        dot: [u8; 3],
+        background: [u8; 3],
+    ) -> io::Result<()> {
+        let (origin, width, height) = self.frame();
+        write!(out, "P6\n{} {}\n255\n", width, height)?;
+        let mut row = Vec::with_capacity(width * 3);
+        for y in 0..height {
+            row.clear();
+            for x in 0..width {
+                let color = if self.contains(origin, x, y) {
+                    dot
+                } else {
+                    background
+                };
+                row.extend_from_slice(&color);
+            }
+            out.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    fn contains(&self, origin: Point, x: usize, y: usize) -> bool {
+        self.0.contains(&Point {
+            x: origin.x + x as i32,
+            y: origin.y + y as i32,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
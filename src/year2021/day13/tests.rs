@@ -86,3 +86,79 @@ mod part2 {
         assert_eq!(&result, EXPECTED_ANSWER);
     }
 }
+
+mod folds {
+    use super::*;
+
+    fn image(dots: &[(i32, i32)]) -> Image {
+        dots.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    #[test]
+    fn parse_diagonal_fold() {
+        assert_eq!("fold along x=y".parse::<Fold>(), Ok(Fold::Diagonal));
+    }
+
+    #[test]
+    fn fold_left_of_center() {
+        let folded = image(&[(0, 0), (1, 2), (10, 0), (6, 1)]).fold(Fold::Left(3));
+
+        assert_eq!(folded, image(&[(4, 0), (5, 2), (0, 0), (4, 1)]));
+    }
+
+    #[test]
+    fn fold_up_off_center() {
+        let folded = image(&[(0, 0), (0, 7), (1, 9)]).fold(Fold::Up(2));
+
+        assert_eq!(folded, image(&[(0, 5), (0, 2), (1, 0)]));
+    }
+
+    #[test]
+    fn fold_diagonal() {
+        let folded = image(&[(3, 1), (1, 3), (2, 2), (0, 4)]).fold(Fold::Diagonal);
+
+        assert_eq!(folded, image(&[(1, 3), (2, 2), (0, 4)]));
+    }
+}
+
+mod export {
+    use super::*;
+
+    fn image(dots: &[(i32, i32)]) -> Image {
+        dots.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    #[test]
+    fn write_pbm() {
+        let mut out = Vec::new();
+
+        image(&[(1, 1), (3, 2), (9, 1)])
+            .write_pbm(&mut out)
+            .unwrap();
+
+        assert_eq!(out, b"P4\n9 2\n\x80\x80\x20\x00");
+    }
+
+    #[test]
+    fn write_ppm() {
+        let mut out = Vec::new();
+
+        image(&[(0, 0), (1, 1)])
+            .write_ppm(&mut out, [255, 0, 0], [0, 0, 0])
+            .unwrap();
+
+        assert_eq!(
+            out,
+            b"P6\n2 2\n255\n\xff\x00\x00\x00\x00\x00\x00\x00\x00\xff\x00\x00"
+        );
+    }
+
+    #[test]
+    fn write_pbm_of_empty_image() {
+        let mut out = Vec::new();
+
+        Image::from_iter([]).write_pbm(&mut out).unwrap();
+
+        assert_eq!(out, b"P4\n0 0\n");
+    }
+}
@@ -184,71 +184,548 @@ pub fn develop_polymer(num_runs: u16, polymer_instructions: &PolymerInstructions
     })
 }
 
-fn count_elements_in_polymer(polymer: &str) -> HashMap<char, u64> {
-    let mut element_counts = HashMap::new();
-    for c in polymer.chars() {
-        *element_counts.entry(c).or_insert(0) += 1;
+/// A square matrix of counts. Arithmetic saturates at `u128::MAX`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Matrix {
+    size: usize,
+    values: Vec<u128>,
+}
+
+impl Matrix {
+    fn zero(size: usize) -> Self {
+        Self {
+            size,
+            values: vec![0; size * size],
+        }
+    }
+
+    fn identity(size: usize) -> Self {
+        let mut matrix = Self::zero(size);
+        for index in 0..size {
+            matrix.values[index * size + index] = 1;
+        }
+        matrix
+    }
+
+    fn get(&self, row: usize, col: usize) -> u128 {
+        self.values[row * self.size + col]
+    }
+
+    fn multiply(&self, other: &Matrix) -> Matrix {
+        let mut product = Matrix::zero(self.size);
+        for row in 0..self.size {
+            for k in 0..self.size {
+                let left = self.get(row, k);
+                if left == 0 {
+                    continue;
+                }
+                for col in 0..self.size {
+                    let value = &mut product.values[row * self.size + col];
+                    *value = value.saturating_add(left.saturating_mul(other.get(k, col)));
+                }
+            }
+        }
+        product
+    }
+
+    fn power(&self, mut exponent: u64) -> Matrix {
+        let mut result = Matrix::identity(self.size);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.multiply(&base);
+            }
+        }
+        result
+    }
+
+    fn apply(&self, vector: &[u128]) -> Vec<u128> {
+        (0..self.size)
+            .map(|row| {
+                vector.iter().enumerate().fold(0u128, |sum, (col, &count)| {
+                    sum.saturating_add(self.get(row, col).saturating_mul(count))
+                })
+            })
+            .collect()
     }
-    element_counts
 }
 
-#[aoc(day14, part1)]
-pub fn solve_part1(polymer_instructions: &PolymerInstructions) -> u64 {
-    //eprintln!("{}", polymer_instructions);
-    let polymer = develop_polymer(10, polymer_instructions);
-    let element_counts = count_elements_in_polymer(&polymer);
-    let most_common = *element_counts
-        .values()
-        .max()
-        .expect("no character in polymer at all");
-    let least_common = *element_counts
-        .values()
-        .min()
-        .expect("no character in polymer at all");
-    most_common - least_common
+/// Develops polymers by counting pairs of elements instead of building the
+/// polymer string.
+///
+/// Pairs of elements are numbered `first * elements.len() + second`. The
+/// transition matrix maps the counts of pairs before a step to the counts
+/// after the step, so `n` steps are the `n`-th power of the matrix. Counts
+/// saturate at `u128::MAX`, while lengths and positions are computed with
+/// checked arithmetic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolymerEngine {
+    elements: Vec<char>,
+    template: Vec<usize>,
+    /// The inserted element of each pair, if any
+    insertions: Vec<Option<usize>>,
+    transitions: Matrix,
 }
 
-fn init_pair_counts(template: &str) -> HashMap<(char, char), u64> {
-    let mut pair_count = HashMap::new();
-    for pair in template.chars().zip(template.chars().skip(1)) {
-        *pair_count.entry(pair).or_insert(0) += 1;
+impl PolymerEngine {
+    pub fn new(polymer_instructions: &PolymerInstructions) -> Self {
+        let mut elements = polymer_instructions
+            .template
+            .chars()
+            .chain(
+                polymer_instructions
+                    .rules
+                    .iter()
+                    .flat_map(|(&(c1, c2), &ci)| [c1, c2, ci]),
+            )
+            .collect::<Vec<_>>();
+        elements.sort_unstable();
+        elements.dedup();
+        let element_index = |c: char| {
+            elements
+                .binary_search(&c)
+                .expect("element of polymer instructions")
+        };
+
+        let num_elements = elements.len();
+        let mut insertions = vec![None; num_elements * num_elements];
+        for (&(c1, c2), &ci) in &polymer_instructions.rules {
+            insertions[element_index(c1) * num_elements + element_index(c2)] =
+                Some(element_index(ci));
+        }
+        let mut transitions = Matrix::zero(insertions.len());
+        for (pair, insertion) in insertions.iter().enumerate() {
+            let (first, second) = (pair / num_elements, pair % num_elements);
+            if let Some(inserted) = insertion {
+                transitions.values[(first * num_elements + inserted) * transitions.size + pair] +=
+                    1;
+                transitions.values[(inserted * num_elements + second) * transitions.size + pair] +=
+                    1;
+            } else {
+                transitions.values[pair * transitions.size + pair] += 1;
+            }
+        }
+
+        let template = polymer_instructions
+            .template
+            .chars()
+            .map(element_index)
+            .collect();
+        Self {
+            elements,
+            template,
+            insertions,
+            transitions,
+        }
     }
-    pair_count
-}
 
-fn develop_polymer2(
-    num_runs: u16,
-    polymer_instructions: &PolymerInstructions,
-) -> HashMap<(char, char), u64> {
-    let mut pair_counts = init_pair_counts(&polymer_instructions.template);
-    for _ in 0..num_runs {
-        let mut next_pair_counts = HashMap::new();
-        for (pair, count) in pair_counts {
-            if let Some(insertion) = polymer_instructions.rules.get(&pair) {
-                *next_pair_counts.entry((pair.0, *insertion)).or_insert(0) += count;
-                *next_pair_counts.entry((*insertion, pair.1)).or_insert(0) += count;
+    fn pair(&self, first: usize, second: usize) -> usize {
+        first * self.elements.len() + second
+    }
+
+    fn split_pair(&self, pair: usize) -> (usize, usize) {
+        (pair / self.elements.len(), pair % self.elements.len())
+    }
+
+    fn pair_count_vector(&self, num_steps: u64) -> Vec<u128> {
+        let mut pair_counts = vec![0; self.insertions.len()];
+        for window in self.template.windows(2) {
+            pair_counts[self.pair(window[0], window[1])] += 1;
+        }
+        self.transitions.power(num_steps).apply(&pair_counts)
+    }
+
+    /// Counts the pairs of adjacent elements after the given number of steps.
+    pub fn pair_counts(&self, num_steps: u64) -> HashMap<(char, char), u128> {
+        self.pair_count_vector(num_steps)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .map(|(pair, count)| {
+                let (first, second) = self.split_pair(pair);
+                ((self.elements[first], self.elements[second]), count)
+            })
+            .collect()
+    }
+
+    /// Counts each element in the polymer after the given number of steps.
+    pub fn histogram(&self, num_steps: u64) -> HashMap<char, u128> {
+        let mut element_counts = HashMap::new();
+        // every element is the first of a pair except for the last one
+        if let Some(&last) = self.template.last() {
+            element_counts.insert(self.elements[last], 1);
+        }
+        for (pair, count) in self.pair_count_vector(num_steps).into_iter().enumerate() {
+            if count > 0 {
+                let (first, _) = self.split_pair(pair);
+                let element_count = element_counts.entry(self.elements[first]).or_insert(0u128);
+                *element_count = element_count.saturating_add(count);
+            }
+        }
+        element_counts
+    }
+
+    /// Returns the length of the polymer after the given number of steps or
+    /// `None` if it is longer than `u128::MAX`.
+    pub fn len(&self, num_steps: u64) -> Option<u128> {
+        if self.template.is_empty() {
+            return Some(0);
+        }
+        let mut lengths = ExpansionLengths::new(&self.transitions, num_steps);
+        self.template.windows(2).try_fold(1u128, |length, window| {
+            length.checked_add(lengths.of(self.pair(window[0], window[1]), num_steps)?)
+        })
+    }
+
+    /// Returns the element at the index of the polymer after the given
+    /// number of steps or `None` if the polymer is not that long.
+    ///
+    /// Walks down the tree of insertions of the pair that contains the index.
+    /// The expansion of the left child of a pair is a prefix of the expansion
+    /// of the pair, and the one of the right child a suffix. So a run of steps
+    /// in the same direction is found by binary search over its length, which
+    /// keeps the walk independent of the number of steps.
+    pub fn char_at(&self, num_steps: u64, index: u128) -> Option<char> {
+        let mut lengths = ExpansionLengths::new(&self.transitions, num_steps);
+
+        let mut pair = None;
+        let mut index = index;
+        for window in self.template.windows(2) {
+            let candidate = self.pair(window[0], window[1]);
+            match lengths.of(candidate, num_steps) {
+                Some(length) if index >= length => index -= length,
+                _ => {
+                    pair = Some(candidate);
+                    break;
+                },
+            }
+        }
+        let Some(mut pair) = pair else {
+            // the last element is not part of any pair's expansion
+            return match index {
+                0 => self.template.last().map(|&last| self.elements[last]),
+                _ => None,
+            };
+        };
+
+        let mut num_steps = num_steps;
+        while index > 0 {
+            let (first, _) = self.split_pair(pair);
+            let inserted = self.insertions[pair].expect("pair without insertion is not expanded");
+            if exceeds(lengths.of(self.pair(first, inserted), num_steps - 1), index) {
+                let chain = self.chain(pair, |first, inserted, _| (first, inserted));
+                let run = longest_run(&chain, num_steps, |child, num_steps| {
+                    exceeds(lengths.of(child, num_steps), index)
+                });
+                pair = chain.get(run);
+                num_steps -= run;
             } else {
-                *next_pair_counts.entry(pair).or_insert(0) += count;
+                let chain = self.chain(pair, |_, inserted, second| (inserted, second));
+                let (run, skipped) = self.longest_right_run(&chain, num_steps, index, &mut lengths);
+                pair = chain.get(run);
+                num_steps -= run;
+                index -= skipped;
+            }
+        }
+        let (first, _) = self.split_pair(pair);
+        Some(self.elements[first])
+    }
+
+    /// Returns the longest run of steps to the right child along the chain
+    /// that skips at most `index` elements, together with the number of
+    /// elements skipped.
+    ///
+    /// Every step skips the expansion of the left child. The run is built
+    /// from the highest power of two down, and the number of elements
+    /// skipped by `2^k` steps is looked up from the expansion lengths at the
+    /// end of the steps and the pairs left behind by them.
+    fn longest_right_run(
+        &self,
+        chain: &Chain,
+        num_steps: u64,
+        index: u128,
+        lengths: &mut ExpansionLengths,
+    ) -> (u64, u128) {
+        let max_steps = chain.max_steps().min(num_steps);
+        let (mut run, mut skipped) = (0, 0);
+        for bit in (0..u64::BITS).rev() {
+            let steps = 1 << bit;
+            if max_steps - run < steps {
+                continue;
+            }
+            let left_behind = self.left_behind(chain, chain.get(run), bit, lengths);
+            let block = lengths.dot(num_steps - run - steps, &left_behind);
+            if let Some(next) = add(Some(skipped), block).filter(|&next| next <= index) {
+                run += steps;
+                skipped = next;
+            }
+        }
+        (run, skipped)
+    }
+
+    /// Returns the pairs left behind by `2^bit` steps to the right child
+    /// starting at the pair, each developed by the steps taken after it was
+    /// left behind.
+    fn left_behind(
+        &self,
+        chain: &Chain,
+        pair: usize,
+        bit: u32,
+        lengths: &mut ExpansionLengths,
+    ) -> Vec<Length> {
+        if let Some(pairs) = lengths.left_behind.get(&(pair, bit)) {
+            return pairs.clone();
+        }
+        let pairs = if bit == 0 {
+            let (first, _) = self.split_pair(pair);
+            let inserted = self.insertions[pair].expect("pair without insertion is not expanded");
+            let mut pairs = vec![Some(0); self.insertions.len()];
+            pairs[self.pair(first, inserted)] = Some(1);
+            pairs
+        } else {
+            let half = self.left_behind(chain, pair, bit - 1, lengths);
+            let position = chain
+                .pairs
+                .iter()
+                .position(|&candidate| candidate == pair)
+                .expect("pair of the chain") as u64;
+            let later = self.left_behind(
+                chain,
+                chain.get(position + (1 << (bit - 1))),
+                bit - 1,
+                lengths,
+            );
+            lengths.powers[bit as usize - 1]
+                .apply(&half)
+                .into_iter()
+                .zip(later)
+                .map(|(half, later)| add(half, later))
+                .collect()
+        };
+        lengths.left_behind.insert((pair, bit), pairs.clone());
+        pairs
+    }
+
+    /// Returns the chain of pairs that starts at the pair and continues with
+    /// the child selected from the elements of the pair and the inserted one.
+    fn chain(&self, start: usize, child: impl Fn(usize, usize, usize) -> (usize, usize)) -> Chain {
+        let mut pairs = vec![start];
+        let mut seen = HashMap::new();
+        seen.insert(start, 0);
+        while let Some(inserted) = self.insertions[*pairs.last().expect("start")] {
+            let (first, second) = self.split_pair(*pairs.last().expect("start"));
+            let (left, right) = child(first, inserted, second);
+            let next = self.pair(left, right);
+            if let Some(&cycle_start) = seen.get(&next) {
+                return Chain {
+                    pairs,
+                    cycle_start: Some(cycle_start),
+                };
             }
+            seen.insert(next, pairs.len());
+            pairs.push(next);
+        }
+        Chain {
+            pairs,
+            cycle_start: None,
         }
-        pair_counts = next_pair_counts;
     }
-    pair_counts
 }
 
-fn count_elements_in_pairs(pair_counts: &HashMap<(char, char), u64>) -> HashMap<char, u64> {
-    let mut element_counts = HashMap::new();
-    for (&(e1, e2), &count) in pair_counts {
-        *element_counts.entry(e1).or_insert(0) += count;
-        *element_counts.entry(e2).or_insert(0) += count;
+/// A chain of pairs that either ends in a pair without insertion or repeats
+/// from `cycle_start` on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Chain {
+    pairs: Vec<usize>,
+    cycle_start: Option<usize>,
+}
+
+impl Chain {
+    fn get(&self, step: u64) -> usize {
+        let step = match self.cycle_start {
+            Some(start) if step >= self.pairs.len() as u64 => {
+                let cycle_len = (self.pairs.len() - start) as u64;
+                start + ((step - start as u64) % cycle_len) as usize
+            },
+            _ => step as usize,
+        };
+        self.pairs[step]
+    }
+
+    /// The number of steps that can be taken along the chain.
+    fn max_steps(&self) -> u64 {
+        match self.cycle_start {
+            Some(_) => u64::MAX,
+            None => self.pairs.len() as u64 - 1,
+        }
     }
-    element_counts
 }
 
-#[aoc(day14, part2)]
-pub fn solve_part2(polymer_instructions: &PolymerInstructions) -> u64 {
-    let pair_counts = develop_polymer2(40, polymer_instructions);
-    let element_counts = count_elements_in_pairs(&pair_counts);
+/// Returns the longest run of steps along the chain that starts with
+/// `num_steps` steps left and keeps the index within the expansions. The
+/// index must be within the expansion after the first step of the run.
+fn longest_run(
+    chain: &Chain,
+    num_steps: u64,
+    mut contains_index: impl FnMut(usize, u64) -> bool,
+) -> u64 {
+    // the expansions along the chain are nested, so the index stays
+    // within all of them up to some step
+    let (mut low, mut high) = (1, chain.max_steps().min(num_steps));
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if contains_index(chain.get(mid), num_steps - mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+/// A number of elements or `None` if it is larger than `u128::MAX`.
+type Length = Option<u128>;
+
+fn add(left: Length, right: Length) -> Length {
+    left?.checked_add(right?)
+}
+
+fn multiply(left: Length, right: Length) -> Length {
+    match (left, right) {
+        (Some(0), _) | (_, Some(0)) => Some(0),
+        _ => left?.checked_mul(right?),
+    }
+}
+
+/// Whether the length exceeds the index, so the element at the index is within
+/// it.
+fn exceeds(length: Length, index: u128) -> bool {
+    length.is_none_or(|length| length > index)
+}
+
+/// A square matrix of lengths with checked arithmetic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LengthMatrix {
+    size: usize,
+    values: Vec<Length>,
+}
+
+impl LengthMatrix {
+    fn get(&self, row: usize, col: usize) -> Length {
+        self.values[row * self.size + col]
+    }
+
+    fn multiply(&self, other: &LengthMatrix) -> LengthMatrix {
+        let values = (0..self.size * self.size)
+            .map(|cell| {
+                let (row, col) = (cell / self.size, cell % self.size);
+                (0..self.size).try_fold(0u128, |sum, k| {
+                    sum.checked_add(multiply(self.get(row, k), other.get(k, col))?)
+                })
+            })
+            .collect();
+        LengthMatrix {
+            size: self.size,
+            values,
+        }
+    }
+
+    /// Multiplies the row vector with the matrix.
+    fn apply_transposed(&self, vector: &[Length]) -> Vec<Length> {
+        (0..self.size)
+            .map(|col| {
+                vector
+                    .iter()
+                    .enumerate()
+                    .try_fold(0u128, |sum, (row, &length)| {
+                        sum.checked_add(multiply(length, self.get(row, col))?)
+                    })
+            })
+            .collect()
+    }
+
+    fn apply(&self, vector: &[Length]) -> Vec<Length> {
+        (0..self.size)
+            .map(|row| {
+                vector
+                    .iter()
+                    .enumerate()
+                    .try_fold(0u128, |sum, (col, &length)| {
+                        sum.checked_add(multiply(self.get(row, col), length)?)
+                    })
+            })
+            .collect()
+    }
+}
+
+impl From<&Matrix> for LengthMatrix {
+    fn from(matrix: &Matrix) -> Self {
+        Self {
+            size: matrix.size,
+            values: matrix.values.iter().map(|&value| Some(value)).collect(),
+        }
+    }
+}
+
+/// Computes how many elements each pair expands to, not counting its second
+/// element, from the powers of the transition matrix.
+struct ExpansionLengths {
+    /// The transition matrix raised to the powers of two
+    powers: Vec<LengthMatrix>,
+    cache: HashMap<u64, Vec<Length>>,
+    /// The pairs left behind by runs to the right child, see
+    /// [`PolymerEngine::left_behind`]
+    left_behind: HashMap<(usize, u32), Vec<Length>>,
+}
+
+impl ExpansionLengths {
+    fn new(transitions: &Matrix, max_steps: u64) -> Self {
+        let mut powers = vec![LengthMatrix::from(transitions)];
+        while powers.len() < u64::BITS as usize && max_steps >> powers.len() > 0 {
+            let last = powers.last().expect("at least one power");
+            powers.push(last.multiply(last));
+        }
+        Self {
+            powers,
+            cache: HashMap::new(),
+            left_behind: HashMap::new(),
+        }
+    }
+
+    fn lengths(&mut self, num_steps: u64) -> &[Length] {
+        let powers = &self.powers;
+        self.cache.entry(num_steps).or_insert_with(|| {
+            // the lengths are the column sums of the matrix power
+            let mut lengths = vec![Some(1); powers[0].size];
+            for (bit, power) in powers.iter().enumerate() {
+                if num_steps >> bit & 1 == 1 {
+                    lengths = power.apply_transposed(&lengths);
+                }
+            }
+            lengths
+        })
+    }
+
+    fn of(&mut self, pair: usize, num_steps: u64) -> Length {
+        self.lengths(num_steps)[pair]
+    }
+
+    /// Returns the number of elements the counted pairs expand to.
+    fn dot(&mut self, num_steps: u64, pair_counts: &[Length]) -> Length {
+        self.lengths(num_steps)
+            .iter()
+            .zip(pair_counts)
+            .try_fold(0u128, |sum, (&length, &count)| {
+                sum.checked_add(multiply(length, count)?)
+            })
+    }
+}
+
+fn difference_of_most_and_least_common(element_counts: &HashMap<char, u128>) -> u128 {
     let most_common = *element_counts
         .values()
         .max()
@@ -257,7 +734,19 @@ pub fn solve_part2(polymer_instructions: &PolymerInstructions) -> u64 {
         .values()
         .min()
         .expect("no character in polymer at all");
-    most_common / 2 - least_common / 2 + 1
+    most_common - least_common
+}
+
+#[aoc(day14, part1)]
+pub fn solve_part1(polymer_instructions: &PolymerInstructions) -> u128 {
+    let engine = PolymerEngine::new(polymer_instructions);
+    difference_of_most_and_least_common(&engine.histogram(10))
+}
+
+#[aoc(day14, part2)]
+pub fn solve_part2(polymer_instructions: &PolymerInstructions) -> u128 {
+    let engine = PolymerEngine::new(polymer_instructions);
+    difference_of_most_and_least_common(&engine.histogram(40))
 }
 
 #[cfg(test)]
@@ -66,3 +66,142 @@ mod part2 {
         assert_eq!(result, 3420801168962);
     }
 }
+
+mod polymer_engine {
+    use super::*;
+
+    #[test]
+    fn histogram_example1() {
+        let engine = PolymerEngine::new(&parse(EXAMPLE1));
+
+        let histogram = engine.histogram(10);
+
+        assert_eq!(histogram.len(), 4);
+        assert_eq!(histogram[&'B'], 1749);
+        assert_eq!(histogram[&'C'], 298);
+        assert_eq!(histogram[&'H'], 161);
+        assert_eq!(histogram[&'N'], 865);
+    }
+
+    #[test]
+    fn histogram_matches_developed_polymer() {
+        let polymer_instructions = parse(EXAMPLE1);
+        let engine = PolymerEngine::new(&polymer_instructions);
+
+        for num_steps in 0..8 {
+            let polymer = develop_polymer(num_steps, &polymer_instructions);
+            let mut expected = HashMap::new();
+            for c in polymer.chars() {
+                *expected.entry(c).or_insert(0u128) += 1;
+            }
+
+            assert_eq!(engine.histogram(num_steps as u64), expected);
+        }
+    }
+
+    #[test]
+    fn pair_counts_example1() {
+        let engine = PolymerEngine::new(&parse(EXAMPLE1));
+
+        let pair_counts = engine.pair_counts(1);
+
+        assert_eq!(
+            pair_counts,
+            HashMap::from_iter([
+                (('N', 'C'), 1),
+                (('C', 'N'), 1),
+                (('N', 'B'), 1),
+                (('B', 'C'), 1),
+                (('C', 'H'), 1),
+                (('H', 'B'), 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn len_example1() {
+        let engine = PolymerEngine::new(&parse(EXAMPLE1));
+
+        assert_eq!(engine.len(0), Some(4));
+        assert_eq!(engine.len(5), Some(97));
+        assert_eq!(engine.len(10), Some(3073));
+        assert_eq!(engine.len(40), Some(3298534883329));
+    }
+
+    #[test]
+    fn char_at_matches_developed_polymer() {
+        let polymer_instructions = parse(EXAMPLE1);
+        let engine = PolymerEngine::new(&polymer_instructions);
+
+        for num_steps in 0..6 {
+            let polymer = develop_polymer(num_steps, &polymer_instructions);
+            for (index, c) in polymer.chars().enumerate() {
+                assert_eq!(engine.char_at(num_steps as u64, index as u128), Some(c));
+            }
+            assert_eq!(
+                engine.char_at(num_steps as u64, polymer.len() as u128),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn char_at_without_matching_rules() {
+        let engine = PolymerEngine::new(&parse("AB\n\nBA -> A\n"));
+
+        assert_eq!(engine.len(1_000_000), Some(2));
+        assert_eq!(engine.char_at(1_000_000, 1), Some('B'));
+        assert_eq!(engine.char_at(1_000_000, 2), None);
+    }
+
+    #[test]
+    fn char_at_with_linearly_growing_polymer() {
+        const NUM_STEPS: u64 = 1_000_000_000;
+        let engine = PolymerEngine::new(&parse("AB\n\nAB -> A\n"));
+
+        assert_eq!(engine.len(NUM_STEPS), Some(u128::from(NUM_STEPS) + 2));
+        assert_eq!(engine.char_at(NUM_STEPS, 0), Some('A'));
+        assert_eq!(engine.char_at(NUM_STEPS, 500_000_000), Some('A'));
+        assert_eq!(engine.char_at(NUM_STEPS, u128::from(NUM_STEPS)), Some('A'));
+        assert_eq!(
+            engine.char_at(NUM_STEPS, u128::from(NUM_STEPS) + 1),
+            Some('B')
+        );
+        assert_eq!(engine.char_at(NUM_STEPS, u128::from(NUM_STEPS) + 2), None);
+
+        let engine = PolymerEngine::new(&parse("AB\n\nAB -> B\n"));
+
+        assert_eq!(engine.char_at(NUM_STEPS, 0), Some('A'));
+        assert_eq!(engine.char_at(NUM_STEPS, 1), Some('B'));
+        assert_eq!(
+            engine.char_at(NUM_STEPS, u128::from(NUM_STEPS) + 1),
+            Some('B')
+        );
+    }
+
+    #[test]
+    fn char_at_with_polymer_longer_than_u128() {
+        const RULES: &str = "AB\n\nAB -> C\nCB -> D\nDB -> C\nCD -> C\nDC -> D\nCC -> D\nDD -> C\n";
+        let engine = PolymerEngine::new(&parse(RULES));
+
+        assert_eq!(engine.len(128), Some((1 << 127) + 2));
+        assert_eq!(engine.len(129), None);
+        for num_steps in [130, 200, 1_000_000] {
+            let prefix = (0..24)
+                .map(|index| engine.char_at(num_steps, index).unwrap())
+                .collect::<String>();
+            assert_eq!(prefix, "ACDCCDCDDCCDDCDCCDCDDCDC");
+        }
+        let prefix = (0..24)
+            .map(|index| engine.char_at(131, index).unwrap())
+            .collect::<String>();
+        assert_eq!(prefix, "ACCDDCDCCDDCCDCDDCDCCDCD");
+
+        let len = engine.len(100).unwrap();
+        let suffix = (len - 24..len)
+            .map(|index| engine.char_at(100, index).unwrap())
+            .collect::<String>();
+        assert_eq!(suffix, "CDDCDCCDDCCDCDDCDCCDDCDB");
+        assert_eq!(engine.char_at(100, len), None);
+    }
+}
@@ -145,9 +145,6 @@
 //!
 //! [Advent of Code 2021 - Day 15](https://adventofcode.com/2021/day/15)
 
-use hashbrown::HashMap;
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, VecDeque};
 use std::fmt::{Display, Formatter};
 use std::ops::Add;
 use std::str::FromStr;
@@ -165,7 +162,8 @@ pub struct Risk {
 
 impl Risk {
     const ZERO: Risk = Risk { level: 0 };
-    const MAX: Risk = Risk { level: u32::MAX };
+    pub const MIN_LEVEL: u32 = 1;
+    pub const MAX_LEVEL: u32 = 9;
 }
 
 impl Add for Risk {
@@ -184,165 +182,270 @@ impl TryFrom<char> for Risk {
     fn try_from(value: char) -> Result<Self, Self::Error> {
         value
             .to_digit(10)
+            .filter(|level| *level >= Risk::MIN_LEVEL)
             .map(|level| Self { level })
-            .ok_or_else(|| format!("invalid digit {}", value))
+            .ok_or_else(|| format!("invalid risk level {}", value))
     }
 }
 
+/// A rectangular grid of risk levels.
+pub trait RiskGrid {
+    fn top_left(&self) -> Point;
+
+    fn bottom_right(&self) -> Point;
+
+    /// Returns the risk level at the point or `None` if the point is not on
+    /// the grid.
+    fn risk(&self, point: Point) -> Option<Risk>;
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RiskMap {
-    /// the risk levels of each point on a grid
-    levels: HashMap<Point, Risk>,
-    top_left: Point,
-    bottom_right: Point,
+    /// the risk levels of each point on a grid, row by row
+    levels: Vec<Risk>,
+    width: i32,
+    height: i32,
 }
 
 impl FromStr for RiskMap {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut levels = HashMap::new();
-        let mut x_max = 0;
-        let mut y_max = 0;
-        for (y, line) in s.lines().filter(|line| !line.is_empty()).enumerate() {
-            y_max = y;
-            for (x, c) in line.chars().enumerate() {
-                x_max = x;
-                let risk = Risk::try_from(c)?;
-                levels.insert(
-                    Point {
-                        x: x as i32,
-                        y: y as i32,
-                    },
-                    risk,
-                );
+        let mut levels = Vec::new();
+        let mut width = 0;
+        let mut height = 0_usize;
+        for line in s.lines().filter(|line| !line.is_empty()) {
+            let row_start = levels.len();
+            for c in line.chars() {
+                levels.push(Risk::try_from(c)?);
             }
+            let row_width = levels.len() - row_start;
+            if height > 0 && row_width != width {
+                return Err(format!("row {} has a different length", height));
+            }
+            width = row_width;
+            height += 1;
         }
         Ok(Self {
             levels,
-            top_left: Point { x: 0, y: 0 },
-            bottom_right: Point {
-                x: x_max as i32,
-                y: y_max as i32,
-            },
+            width: width as i32,
+            height: height as i32,
         })
     }
 }
 
 impl Display for RiskMap {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for y in self.top_left.y..=self.bottom_right.y {
-            for x in self.top_left().x..=self.bottom_right.x {
-                write!(f, "{}", self.levels[&Point { x, y }].level)?;
+        for row in self.levels.chunks(self.width.max(1) as usize) {
+            for risk in row {
+                write!(f, "{}", risk.level)?;
             }
-            writeln!(f, "")?;
+            writeln!(f)?;
         }
         Ok(())
     }
 }
 
 impl RiskMap {
-    pub fn top_left(&self) -> Point {
-        self.top_left
+    pub fn width(&self) -> i32 {
+        self.width
     }
 
-    pub fn bottom_right(&self) -> Point {
-        self.bottom_right
+    pub fn height(&self) -> i32 {
+        self.height
     }
 
     pub fn get_point(&self, point: Point) -> Option<Point> {
-        self.levels.get(&point).map(|_| point)
+        self.risk(point).map(|_| point)
     }
+}
 
-    pub fn risk(&self, point: Point) -> Option<Risk> {
-        self.levels.get(&point).copied()
+impl RiskGrid for RiskMap {
+    fn top_left(&self) -> Point {
+        Point { x: 0, y: 0 }
     }
-}
 
-fn neighbors(Point { x, y }: Point, map: &RiskMap) -> Vec<Point> {
-    const NEIGHBOR_OFFSETS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-    NEIGHBOR_OFFSETS
-        .iter()
-        .filter_map(|offset| {
-            map.get_point(Point {
-                x: x + offset.0,
-                y: y + offset.1,
-            })
-        })
-        .collect()
+    fn bottom_right(&self) -> Point {
+        Point {
+            x: self.width - 1,
+            y: self.height - 1,
+        }
+    }
+
+    fn risk(&self, Point { x, y }: Point) -> Option<Risk> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.levels[(y * self.width + x) as usize])
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Node {
-    point: Point,
-    risk: Risk,
+/// A risk map repeated `factor` times to the right and downwards without
+/// storing the repeated tiles.
+///
+/// Each tile to the right or downwards has all risk levels increased by 1,
+/// where levels above 9 wrap back around to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TiledRiskMap<'a> {
+    base: &'a RiskMap,
+    factor: i32,
 }
 
-impl PartialEq for Node {
-    fn eq(&self, other: &Self) -> bool {
-        self.risk.eq(&other.risk)
+impl<'a> TiledRiskMap<'a> {
+    pub fn new(base: &'a RiskMap, factor: i32) -> Self {
+        Self { base, factor }
+    }
+
+    pub fn factor(&self) -> i32 {
+        self.factor
     }
 }
 
-impl Eq for Node {}
+impl RiskGrid for TiledRiskMap<'_> {
+    fn top_left(&self) -> Point {
+        Point { x: 0, y: 0 }
+    }
+
+    fn bottom_right(&self) -> Point {
+        Point {
+            x: self.base.width * self.factor - 1,
+            y: self.base.height * self.factor - 1,
+        }
+    }
 
-impl PartialOrd for Node {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.risk.level.partial_cmp(&self.risk.level)
+    fn risk(&self, Point { x, y }: Point) -> Option<Risk> {
+        if x < 0
+            || y < 0
+            || x >= self.base.width * self.factor
+            || y >= self.base.height * self.factor
+        {
+            return None;
+        }
+        let (tile_x, tile_y) = (x / self.base.width, y / self.base.height);
+        let base = self.base.risk(Point {
+            x: x % self.base.width,
+            y: y % self.base.height,
+        })?;
+        let level = (base.level - Risk::MIN_LEVEL + (tile_x + tile_y) as u32) % Risk::MAX_LEVEL
+            + Risk::MIN_LEVEL;
+        Some(Risk { level })
     }
 }
 
-impl Ord for Node {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.risk.level.cmp(&self.risk.level)
+const NEIGHBOR_OFFSETS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+/// A priority queue for small integer priorities that grow monotonically
+/// within a bounded window, like the estimated total risk of A*.
+#[derive(Debug, Clone)]
+struct BucketQueue<T> {
+    buckets: Vec<Vec<T>>,
+    current: u32,
+    len: usize,
+}
+
+impl<T> BucketQueue<T> {
+    /// Creates a queue for priorities up to `window - 1` above the priority
+    /// popped last, or above the first one pushed into the empty queue.
+    fn new(window: usize) -> Self {
+        Self {
+            buckets: (0..window).map(|_| Vec::new()).collect(),
+            current: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, priority: u32, item: T) {
+        if self.len == 0 {
+            self.current = priority;
+        }
+        debug_assert!(
+            priority >= self.current && priority < self.current + self.buckets.len() as u32,
+            "priority out of window"
+        );
+        let window = self.buckets.len();
+        self.buckets[priority as usize % window].push(item);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<(u32, T)> {
+        if self.len == 0 {
+            return None;
+        }
+        let window = self.buckets.len();
+        loop {
+            if let Some(item) = self.buckets[self.current as usize % window].pop() {
+                self.len -= 1;
+                return Some((self.current, item));
+            }
+            self.current += 1;
+        }
     }
 }
 
-fn find_path(start: Point, goal: Point, risk_map: &RiskMap) -> (Vec<Point>, Risk) {
-    let mut open = BinaryHeap::new();
-    let mut visited: HashMap<Point, (Risk, Option<Point>)> = HashMap::new();
-    visited.insert(start, (Risk::ZERO, None));
-    open.push(Node {
-        point: start,
-        risk: Risk::ZERO,
-    });
-    while let Some(Node {
-        point: current,
-        risk,
-    }) = open.pop()
-    {
+/// Finds the path of lowest total risk with A*, using the manhattan distance
+/// to the goal as heuristic.
+///
+/// As each step costs at least 1, the heuristic never overestimates. Along
+/// any step the estimated total risk grows by at most the step's risk plus 1,
+/// so a bucket queue covering that range suffices.
+///
+/// # Panics
+///
+/// Panics if the goal can not be reached.
+fn find_path(start: Point, goal: Point, risk_map: &impl RiskGrid) -> (Vec<Point>, Risk) {
+    const NO_STEP: u8 = u8::MAX;
+    let top_left = risk_map.top_left();
+    let bottom_right = risk_map.bottom_right();
+    let width = (bottom_right.x - top_left.x + 1) as usize;
+    let height = (bottom_right.y - top_left.y + 1) as usize;
+    let index =
+        |point: Point| (point.y - top_left.y) as usize * width + (point.x - top_left.x) as usize;
+    let heuristic = |point: Point| ((goal.x - point.x).abs() + (goal.y - point.y).abs()) as u32;
+
+    let mut lowest = vec![u32::MAX; width * height];
+    let mut came_from = vec![NO_STEP; width * height];
+    let mut open = BucketQueue::new((Risk::MAX_LEVEL + 2) as usize);
+    lowest[index(start)] = 0;
+    open.push(heuristic(start), start);
+    while let Some((estimate, current)) = open.pop() {
+        let risk = lowest[index(current)];
+        if estimate != risk + heuristic(current) {
+            continue;
+        }
         if current == goal {
             break;
         }
 
-        for neighbor in neighbors(current, risk_map) {
-            let new_risk = risk + risk_map.risk(neighbor).unwrap();
-            if new_risk
-                < *visited
-                    .get(&neighbor)
-                    .map(|(risk, _)| risk)
-                    .unwrap_or(&Risk::MAX)
-            {
-                visited.insert(neighbor, (new_risk, Some(current)));
-                open.push(Node {
-                    point: neighbor,
-                    risk: new_risk,
-                });
+        for (step, (dx, dy)) in NEIGHBOR_OFFSETS.iter().enumerate() {
+            let neighbor = Point {
+                x: current.x + dx,
+                y: current.y + dy,
+            };
+            if let Some(level) = risk_map.risk(neighbor) {
+                let new_risk = risk + level.level;
+                if new_risk < lowest[index(neighbor)] {
+                    lowest[index(neighbor)] = new_risk;
+                    came_from[index(neighbor)] = step as u8;
+                    open.push(new_risk + heuristic(neighbor), neighbor);
+                }
             }
         }
     }
 
-    let mut path = VecDeque::new();
-    path.push_front(goal);
+    let total_risk = lowest[index(goal)];
+    assert!(total_risk != u32::MAX, "no calculated risk for goal");
+    let mut path = vec![goal];
     let mut current = goal;
-    while let Some(&Some(step)) = visited.get(&current).map(|(_, point)| point) {
-        path.push_front(step);
-        current = step;
+    while current != start {
+        let (dx, dy) = NEIGHBOR_OFFSETS[came_from[index(current)] as usize];
+        current = Point {
+            x: current.x - dx,
+            y: current.y - dy,
+        };
+        path.push(current);
     }
-
-    let path = Vec::from_iter(path.into_iter());
-    let (risk, _) = *visited.get(&goal).expect("no calculated risk for goal");
-    (path, risk)
+    path.reverse();
+    (path, Risk::ZERO + Risk { level: total_risk })
 }
 
 #[aoc_generator(day15)]
@@ -352,59 +455,21 @@ pub fn parse(input: &str) -> RiskMap {
         .unwrap_or_else(|err| panic!("{}", err))
 }
 
-#[aoc(day15, part1)]
-pub fn solve_part1(risk_map: &RiskMap) -> u32 {
-    // eprintln!("{}", risk_map);
+fn lowest_total_risk(risk_map: &impl RiskGrid) -> u32 {
     let start = risk_map.top_left();
     let goal = risk_map.bottom_right();
     let (_path, total_risk) = find_path(start, goal, risk_map);
-    // eprintln!("{:?}", &_path);
     total_risk.level
 }
 
-fn expand_risk_map(factor: i32, risk_map: &RiskMap) -> RiskMap {
-    let width = risk_map.bottom_right.x - risk_map.top_left.x + 1;
-    let height = risk_map.bottom_right.y - risk_map.top_left.y + 1;
-    let x_max = width * 5 - 1;
-    let y_max = height * 5 - 1;
-    let mut levels = HashMap::with_capacity(risk_map.levels.len());
-    for y_tile in 0..factor {
-        for x_tile in 0..factor {
-            for (point, risk) in &risk_map.levels {
-                let new_point = Point {
-                    x: point.x + x_tile * width,
-                    y: point.y + y_tile * height,
-                };
-                let mut new_risk_level = risk.level + x_tile as u32 + y_tile as u32;
-                if new_risk_level > 9 {
-                    new_risk_level = new_risk_level - 9;
-                }
-                levels.insert(
-                    new_point,
-                    Risk {
-                        level: new_risk_level,
-                    },
-                );
-            }
-        }
-    }
-
-    RiskMap {
-        levels,
-        top_left: Point { x: 0, y: 0 },
-        bottom_right: Point { x: x_max, y: y_max },
-    }
+#[aoc(day15, part1)]
+pub fn solve_part1(risk_map: &RiskMap) -> u32 {
+    lowest_total_risk(risk_map)
 }
 
 #[aoc(day15, part2)]
 pub fn solve_part2(risk_map: &RiskMap) -> u32 {
-    let risk_map = expand_risk_map(5, risk_map);
-    // eprintln!("{}", &risk_map);
-    let start = risk_map.top_left();
-    let goal = risk_map.bottom_right();
-    let (_path, total_risk) = find_path(start, goal, &risk_map);
-    // eprintln!("{:?}", &_path);
-    total_risk.level
+    lowest_total_risk(&TiledRiskMap::new(risk_map, 5))
 }
 
 #[cfg(test)]
@@ -15,6 +15,94 @@ const EXAMPLE1: &str = "\
 2311944581
 ";
 
+mod tiled_risk_map {
+    use super::*;
+
+    #[test]
+    fn risk_in_tiles_example1() {
+        let risk_map = parse(EXAMPLE1);
+        let tiled = TiledRiskMap::new(&risk_map, 5);
+
+        assert_eq!(tiled.bottom_right(), Point { x: 49, y: 49 });
+        assert_eq!(tiled.risk(Point { x: 0, y: 0 }), Some(Risk { level: 1 }));
+        assert_eq!(tiled.risk(Point { x: 10, y: 0 }), Some(Risk { level: 2 }));
+        assert_eq!(tiled.risk(Point { x: 49, y: 49 }), Some(Risk { level: 9 }));
+        assert_eq!(tiled.risk(Point { x: 44, y: 44 }), Some(Risk { level: 3 }));
+        assert_eq!(tiled.risk(Point { x: 12, y: 1 }), Some(Risk { level: 9 }));
+        assert_eq!(tiled.risk(Point { x: 22, y: 1 }), Some(Risk { level: 1 }));
+        assert_eq!(tiled.risk(Point { x: 50, y: 0 }), None);
+        assert_eq!(tiled.risk(Point { x: 0, y: -1 }), None);
+    }
+
+    #[test]
+    fn risk_wraps_around_for_large_factors() {
+        let risk_map = parse("9\n");
+        let tiled = TiledRiskMap::new(&risk_map, 50);
+
+        assert_eq!(tiled.risk(Point { x: 1, y: 0 }), Some(Risk { level: 1 }));
+        assert_eq!(tiled.risk(Point { x: 9, y: 9 }), Some(Risk { level: 9 }));
+        assert_eq!(tiled.risk(Point { x: 49, y: 49 }), Some(Risk { level: 8 }));
+    }
+
+    #[test]
+    fn single_tile_equals_base_map() {
+        let risk_map = parse(EXAMPLE1);
+        let tiled = TiledRiskMap::new(&risk_map, 1);
+
+        assert_eq!(tiled.bottom_right(), risk_map.bottom_right());
+        for y in 0..10 {
+            for x in 0..10 {
+                assert_eq!(tiled.risk(Point { x, y }), risk_map.risk(Point { x, y }));
+            }
+        }
+    }
+
+    #[test]
+    fn find_path_example1() {
+        let risk_map = parse(EXAMPLE1);
+        let tiled = TiledRiskMap::new(&risk_map, 5);
+
+        let (path, total_risk) = find_path(tiled.top_left(), tiled.bottom_right(), &tiled);
+
+        assert_eq!(total_risk, Risk { level: 315 });
+        assert_eq!(path.first(), Some(&Point { x: 0, y: 0 }));
+        assert_eq!(path.last(), Some(&Point { x: 49, y: 49 }));
+        assert!(path
+            .windows(2)
+            .all(|step| (step[0].x - step[1].x).abs() + (step[0].y - step[1].y).abs() == 1));
+        assert_eq!(
+            path.iter()
+                .skip(1)
+                .map(|point| tiled.risk(*point).unwrap().level)
+                .sum::<u32>(),
+            315
+        );
+    }
+
+    #[test]
+    fn lowest_total_risk_with_factor_50_example1() {
+        let risk_map = parse(EXAMPLE1);
+        let tiled = TiledRiskMap::new(&risk_map, 50);
+
+        let (path, total_risk) = find_path(tiled.top_left(), tiled.bottom_right(), &tiled);
+
+        assert_eq!(path.first(), Some(&Point { x: 0, y: 0 }));
+        assert_eq!(path.last(), Some(&Point { x: 499, y: 499 }));
+        assert_eq!(
+            path.iter()
+                .skip(1)
+                .map(|point| tiled.risk(*point).unwrap().level)
+                .sum::<u32>(),
+            total_risk.level
+        );
+    }
+
+    #[test]
+    fn parse_rejects_risk_level_0() {
+        assert!("120\n".parse::<RiskMap>().is_err());
+    }
+}
+
 mod part1 {
     use super::*;
 
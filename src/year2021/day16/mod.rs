@@ -210,11 +210,23 @@
 //!
 //! [Advent of Code 2021 - Day 16](https://adventofcode.com/2021/day/16)
 
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ast(pub Entry);
+
+/// A decoded packet with the version and length type it was transmitted with.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Ast(Entry);
+pub struct Entry {
+    pub version: u8,
+    /// How the length of the sub-packets is given, `None` for literal values
+    pub length_type: Option<LengthType>,
+    pub operation: Operation,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Entry {
+pub enum Operation {
     Number(i64),
     Sum(Vec<Entry>),
     Product(Vec<Entry>),
@@ -237,327 +249,581 @@ pub enum Length {
     NumberOfSubPackets(u32),
 }
 
-fn hex_to_binary(chr: char) -> Result<&'static str, String> {
-    match chr {
-        '0' => Ok("0000"),
-        '1' => Ok("0001"),
-        '2' => Ok("0010"),
-        '3' => Ok("0011"),
-        '4' => Ok("0100"),
-        '5' => Ok("0101"),
-        '6' => Ok("0110"),
-        '7' => Ok("0111"),
-        '8' => Ok("1000"),
-        '9' => Ok("1001"),
-        'A' => Ok("1010"),
-        'B' => Ok("1011"),
-        'C' => Ok("1100"),
-        'D' => Ok("1101"),
-        'E' => Ok("1110"),
-        'F' => Ok("1111"),
-        _ => Err(format!("not a hexadecimal character: {}", chr)),
+impl Length {
+    pub fn length_type(self) -> LengthType {
+        match self {
+            Length::TotalLength(_) => LengthType::TotalLength,
+            Length::NumberOfSubPackets(_) => LengthType::NumberOfSubPackets,
+        }
     }
 }
 
-#[aoc_generator(day16)]
-pub fn parse(input: &str) -> String {
-    let line = input
-        .lines()
-        .filter(|line| !line.is_empty())
-        .next()
-        .expect("no input line");
-    let mut binary_string = String::new();
-    for chr in line.chars() {
-        let bit_str = hex_to_binary(chr).unwrap_or_else(|err| panic!("{}", err));
-        binary_string.push_str(bit_str);
-    }
-    binary_string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthType {
+    TotalLength,
+    NumberOfSubPackets,
 }
 
-fn decode_version(input: &str) -> (u8, &str, usize) {
-    let (version_str, rest) = input.split_at(3);
-    let version = u8::from_str_radix(version_str, 2)
-        .unwrap_or_else(|err| panic!("not an integer: {} - {}", version_str, err));
-    (version, rest, 3)
+impl Operation {
+    pub fn type_id(&self) -> u8 {
+        match self {
+            Operation::Sum(_) => 0,
+            Operation::Product(_) => 1,
+            Operation::Minimum(_) => 2,
+            Operation::Maximum(_) => 3,
+            Operation::Number(_) => 4,
+            Operation::GreaterThan(_, _) => 5,
+            Operation::LessThan(_, _) => 6,
+            Operation::EqualTo(_, _) => 7,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Operation::Number(_) => "",
+            Operation::Sum(_) => "+",
+            Operation::Product(_) => "*",
+            Operation::Minimum(_) => "min",
+            Operation::Maximum(_) => "max",
+            Operation::GreaterThan(_, _) => ">",
+            Operation::LessThan(_, _) => "<",
+            Operation::EqualTo(_, _) => "=",
+        }
+    }
+
+    pub fn operands(&self) -> Vec<&Entry> {
+        match self {
+            Operation::Number(_) => Vec::new(),
+            Operation::Sum(entries)
+            | Operation::Product(entries)
+            | Operation::Minimum(entries)
+            | Operation::Maximum(entries) => entries.iter().collect(),
+            Operation::GreaterThan(entry1, entry2)
+            | Operation::LessThan(entry1, entry2)
+            | Operation::EqualTo(entry1, entry2) => vec![entry1, entry2],
+        }
+    }
 }
 
-fn decode_type_id(input: &str) -> (u8, &str, usize) {
-    let (type_id_str, rest) = input.split_at(3);
-    let type_id = u8::from_str_radix(type_id_str, 2)
-        .unwrap_or_else(|err| panic!("not an integer: {} - {}", type_id_str, err));
-    (type_id, rest, 3)
+impl Entry {
+    /// Creates a packet of version 0. Operators give the total length of
+    /// their sub-packets.
+    pub fn new(operation: Operation) -> Self {
+        let length_type = match operation {
+            Operation::Number(_) => None,
+            _ => Some(LengthType::TotalLength),
+        };
+        Self {
+            version: 0,
+            length_type,
+            operation,
+        }
+    }
+
+    pub fn number(number: i64) -> Self {
+        Self::new(Operation::Number(number))
+    }
+
+    pub fn with_version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets how the length of sub-packets is given. Has no effect on literal
+    /// values.
+    pub fn with_length_type(mut self, length_type: LengthType) -> Self {
+        if self.length_type.is_some() {
+            self.length_type = Some(length_type);
+        }
+        self
+    }
+
+    pub fn header(&self) -> Header {
+        Header {
+            version: self.version,
+            type_id: self.operation.type_id(),
+        }
+    }
+
+    pub fn version_sum(&self) -> u32 {
+        self.version as u32
+            + self
+                .operation
+                .operands()
+                .iter()
+                .map(|entry| entry.version_sum())
+                .sum::<u32>()
+    }
+
+    fn fmt_sexpr(&self, f: &mut Formatter<'_>, indent: usize) -> fmt::Result {
+        if let Operation::Number(number) = self.operation {
+            return write!(f, "{}", number);
+        }
+        let operands = self.operation.operands();
+        let multiline = f.alternate()
+            && operands
+                .iter()
+                .any(|operand| !matches!(operand.operation, Operation::Number(_)));
+        write!(f, "({}", self.operation.symbol())?;
+        for operand in operands {
+            if multiline {
+                write!(f, "\n{:width$}", "", width = indent + 2)?;
+            } else {
+                write!(f, " ")?;
+            }
+            operand.fmt_sexpr(f, indent + 2)?;
+        }
+        write!(f, ")")
+    }
 }
 
-fn decode_header(input: &str) -> (Header, &str, usize) {
-    let (version, rest, consumed1) = decode_version(input);
-    let (type_id, rest, consumed2) = decode_type_id(rest);
-    (Header { version, type_id }, rest, consumed1 + consumed2)
+/// Renders the tree as an S-expression like `(+ 1 (* 2 3))`. The alternate
+/// form `{:#}` puts operands on separate lines unless they are all numbers.
+impl Display for Ast {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt_sexpr(f, 0)
+    }
 }
 
-fn decode_number(input: &str) -> (i64, &str, usize) {
-    let mut consumed = 0;
-    let mut input = input;
-    let mut number = 0;
-    let mut keep_reading = true;
-    while keep_reading {
-        let (group, rest) = input.split_at(5);
-        consumed += 5;
-        let (more, digit) = group.split_at(1);
-        number = number * 16
-            + i64::from_str_radix(digit, 2)
-                .unwrap_or_else(|err| panic!("not a binary number: {} - {}", digit, err));
-        input = rest;
-        keep_reading = more == "1";
-    }
-    (number, input, consumed)
+fn parse_sexpr<'a>(
+    token: &str,
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<Entry, String> {
+    if token != "(" {
+        return token
+            .parse::<i64>()
+            .map(Entry::number)
+            .map_err(|err| format!("not a number {:?}: {}", token, err));
+    }
+    let symbol = tokens
+        .next()
+        .ok_or_else(|| "missing operator".to_string())?;
+    let mut operands = Vec::new();
+    loop {
+        match tokens.next() {
+            Some(")") => break,
+            Some(token) => operands.push(parse_sexpr(token, tokens)?),
+            None => return Err("missing closing parenthesis".into()),
+        }
+    }
+    let binary = |operands: Vec<Entry>| -> Result<(Box<Entry>, Box<Entry>), String> {
+        let mut operands = operands.into_iter();
+        match (operands.next(), operands.next(), operands.next()) {
+            (Some(entry1), Some(entry2), None) => Ok((Box::new(entry1), Box::new(entry2))),
+            _ => Err(format!("operator {} needs exactly 2 operands", symbol)),
+        }
+    };
+    if operands.is_empty() && ["+", "*", "min", "max"].contains(&symbol) {
+        return Err(format!("operator {} needs at least 1 operand", symbol));
+    }
+    let operation = match symbol {
+        "+" => Operation::Sum(operands),
+        "*" => Operation::Product(operands),
+        "min" => Operation::Minimum(operands),
+        "max" => Operation::Maximum(operands),
+        ">" => binary(operands).map(|(e1, e2)| Operation::GreaterThan(e1, e2))?,
+        "<" => binary(operands).map(|(e1, e2)| Operation::LessThan(e1, e2))?,
+        "=" => binary(operands).map(|(e1, e2)| Operation::EqualTo(e1, e2))?,
+        _ => return Err(format!("unknown operator {:?}", symbol)),
+    };
+    Ok(Entry::new(operation))
 }
 
-fn decode_length(input: &str) -> (Length, &str, usize) {
-    let (length_type, rest) = input.split_at(1);
-    match length_type {
-        "0" => {
-            let (length_str, rest) = rest.split_at(15);
-            let total_length = usize::from_str_radix(length_str, 2)
-                .unwrap_or_else(|err| panic!("not a valid length: {} - {}", length_str, err));
-            (Length::TotalLength(total_length), rest, 16)
+/// Parses an S-expression as rendered by `Display` into packets of version 0.
+impl FromStr for Ast {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spaced = s.replace('(', " ( ").replace(')', " ) ");
+        let mut tokens = spaced.split_whitespace();
+        let first = tokens
+            .next()
+            .ok_or_else(|| "empty expression".to_string())?;
+        let entry = parse_sexpr(first, &mut tokens)?;
+        match tokens.next() {
+            None => Ok(Ast(entry)),
+            Some(token) => Err(format!("unexpected {:?} after expression", token)),
         }
-        "1" => {
-            let (num_subpackets_str, rest) = rest.split_at(11);
-            let num_subpackets = u32::from_str_radix(num_subpackets_str, 2).unwrap_or_else(|err| {
-                panic!(
-                    "not a valid number of subpackets: {} - {}",
-                    num_subpackets_str, err
-                )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The transmission ends in the middle of a packet.
+    Truncated { position: usize },
+    /// A packet has a type id without defined meaning.
+    UnknownTypeId { position: usize, type_id: u8 },
+    /// An operator packet has the wrong number of sub-packets.
+    InvalidOperands { position: usize, type_id: u8 },
+    /// The sub-packets of an operator packet do not end at its total length.
+    LengthMismatch { position: usize },
+    /// A literal value does not fit into 63 bits.
+    NumberTooLarge { position: usize },
+    /// There are bits other than zero padding after the outermost packet.
+    TrailingGarbage { position: usize },
+}
+
+/// Reads bits most significant first from a byte slice.
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    /// Returns the number of bits read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+
+    /// Reads the next `n` bits as an unsigned number. Fails without
+    /// consuming anything if fewer than `n` bits are left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than 64 bits are requested.
+    pub fn read_bits(&mut self, n: usize) -> Result<u64, DecodeError> {
+        assert!(n <= 64, "can not read more than 64 bits at once");
+        if n > self.remaining() {
+            return Err(DecodeError::Truncated {
+                position: self.position,
             });
-            (Length::NumberOfSubPackets(num_subpackets), rest, 12)
         }
-        _ => panic!("invalid length type: {}", length_type),
+        let mut value = 0;
+        for _ in 0..n {
+            let byte = self.bytes[self.position / 8];
+            let bit = byte >> (7 - self.position % 8) & 1;
+            value = value << 1 | bit as u64;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, DecodeError> {
+        self.read_bits(1).map(|bit| bit == 1)
     }
 }
 
-#[aoc(day16, part1)]
-pub fn solve_part1(message: &str) -> u32 {
-    let mut open = Vec::new();
-    let (header, rest, _) = decode_header(message);
-    let mut version_sum = 0;
-    open.push((header, rest));
-    while let Some((header, input)) = open.pop() {
-        version_sum += header.version as u32;
-        let rest = match header.type_id {
-            4 => {
-                let (_number, rest, _) = decode_number(input);
-                rest
-            }
-            _ => {
-                let (_length, rest, _) = decode_length(input);
-                rest
-            }
-        };
-        if rest.len() > 10 {
-            let (header, rest, _) = decode_header(rest);
-            open.push((header, rest));
+fn hex_digit(chr: char) -> Result<u8, String> {
+    chr.to_digit(16)
+        .filter(|_| !chr.is_ascii_lowercase())
+        .map(|digit| digit as u8)
+        .ok_or_else(|| format!("not a hexadecimal character: {}", chr))
+}
+
+/// Converts a hexadecimal transmission into bytes. An odd number of digits is
+/// padded with a zero digit.
+#[aoc_generator(day16)]
+pub fn parse(input: &str) -> Vec<u8> {
+    let line = input
+        .lines()
+        .find(|line| !line.is_empty())
+        .expect("no input line");
+    let digits = line
+        .chars()
+        .map(|chr| hex_digit(chr).unwrap_or_else(|err| panic!("{}", err)))
+        .collect::<Vec<_>>();
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+fn decode_header(reader: &mut BitReader<'_>) -> Result<Header, DecodeError> {
+    let version = reader.read_bits(3)? as u8;
+    let type_id = reader.read_bits(3)? as u8;
+    Ok(Header { version, type_id })
+}
+
+fn decode_number(reader: &mut BitReader<'_>) -> Result<i64, DecodeError> {
+    let position = reader.position();
+    let mut number: i64 = 0;
+    loop {
+        let more = reader.read_bit()?;
+        let digit = reader.read_bits(4)? as i64;
+        number = number
+            .checked_mul(16)
+            .map(|number| number | digit)
+            .ok_or(DecodeError::NumberTooLarge { position })?;
+        if !more {
+            return Ok(number);
         }
     }
-    version_sum
 }
 
-fn decode_operation(header: Header, data: Vec<Entry>) -> Result<Entry, String> {
+fn decode_length(reader: &mut BitReader<'_>) -> Result<Length, DecodeError> {
+    if reader.read_bit()? {
+        let num_subpackets = reader.read_bits(11)? as u32;
+        Ok(Length::NumberOfSubPackets(num_subpackets))
+    } else {
+        let total_length = reader.read_bits(15)? as usize;
+        Ok(Length::TotalLength(total_length))
+    }
+}
+
+#[aoc(day16, part1)]
+pub fn solve_part1(message: &[u8]) -> u32 {
+    let ast = decode_message(message).unwrap_or_else(|err| panic!("{:?}", err));
+    ast.0.version_sum()
+}
+
+fn decode_operation(
+    header: Header,
+    position: usize,
+    data: Vec<Entry>,
+) -> Result<Operation, DecodeError> {
+    let invalid_operands = DecodeError::InvalidOperands {
+        position,
+        type_id: header.type_id,
+    };
+    let binary = |data: Vec<Entry>| {
+        let mut data = data.into_iter();
+        match (data.next(), data.next(), data.next()) {
+            (Some(data1), Some(data2), None) => Ok((Box::new(data1), Box::new(data2))),
+            _ => Err(invalid_operands),
+        }
+    };
     match header.type_id {
-        0 => Ok(Entry::Sum(data)),
-        1 => Ok(Entry::Product(data)),
-        2 => Ok(Entry::Minimum(data)),
-        3 => Ok(Entry::Maximum(data)),
-        5 => {
-            let mut data = data.into_iter();
-            let data1 = data
-                .next()
-                .ok_or_else(|| format!("no operand for greater than operation"))?;
-            let data2 = data
-                .next()
-                .ok_or_else(|| format!("missing second operand for greater than operation"))?;
-            if data.next().is_some() {
-                return Err(format!("more than 2 operands for greater than operation"));
+        0..=3 if data.is_empty() => Err(invalid_operands),
+        0 => Ok(Operation::Sum(data)),
+        1 => Ok(Operation::Product(data)),
+        2 => Ok(Operation::Minimum(data)),
+        3 => Ok(Operation::Maximum(data)),
+        5 => binary(data).map(|(data1, data2)| Operation::GreaterThan(data1, data2)),
+        6 => binary(data).map(|(data1, data2)| Operation::LessThan(data1, data2)),
+        7 => binary(data).map(|(data1, data2)| Operation::EqualTo(data1, data2)),
+        _ => Err(DecodeError::UnknownTypeId {
+            position,
+            type_id: header.type_id,
+        }),
+    }
+}
+
+fn decode_packet(reader: &mut BitReader<'_>) -> Result<Entry, DecodeError> {
+    let position = reader.position();
+    let header = decode_header(reader)?;
+    if header.type_id == 4 {
+        let number = decode_number(reader)?;
+        return Ok(Entry {
+            version: header.version,
+            length_type: None,
+            operation: Operation::Number(number),
+        });
+    }
+    let length = decode_length(reader)?;
+    let mut data = Vec::new();
+    match length {
+        Length::TotalLength(total_length) => {
+            let end = reader.position() + total_length;
+            if total_length > reader.remaining() {
+                return Err(DecodeError::Truncated {
+                    position: reader.position(),
+                });
             }
-            Ok(Entry::GreaterThan(Box::new(data1), Box::new(data2)))
-        }
-        6 => {
-            let mut data = data.into_iter();
-            let data1 = data
-                .next()
-                .ok_or_else(|| format!("no operand for less than operation"))?;
-            let data2 = data
-                .next()
-                .ok_or_else(|| format!("missing second operand for less than operation"))?;
-            if data.next().is_some() {
-                return Err(format!("more than 2 operands for less than operation"));
+            while reader.position() < end {
+                data.push(decode_packet(reader)?);
             }
-            Ok(Entry::LessThan(Box::new(data1), Box::new(data2)))
-        }
-        7 => {
-            let mut data = data.into_iter();
-            let data1 = data
-                .next()
-                .ok_or_else(|| format!("no operand for equal to operation"))?;
-            let data2 = data
-                .next()
-                .ok_or_else(|| format!("missing second operand for equal to operation"))?;
-            if data.next().is_some() {
-                return Err(format!("more than 2 operands for equal to operation"));
+            if reader.position() != end {
+                return Err(DecodeError::LengthMismatch { position });
+            }
+        },
+        Length::NumberOfSubPackets(num_sub_packets) => {
+            for _ in 0..num_sub_packets {
+                data.push(decode_packet(reader)?);
             }
-            Ok(Entry::EqualTo(Box::new(data1), Box::new(data2)))
+        },
+    }
+    Ok(Entry {
+        version: header.version,
+        length_type: Some(length.length_type()),
+        operation: decode_operation(header, position, data)?,
+    })
+}
+
+/// Decodes the outermost packet of the transmission. Only zero bits may
+/// follow it as padding.
+pub fn decode_message(message: &[u8]) -> Result<Ast, DecodeError> {
+    let mut reader = BitReader::new(message);
+    let entry = decode_packet(&mut reader)?;
+    while reader.remaining() > 0 {
+        let position = reader.position();
+        if reader.read_bit()? {
+            return Err(DecodeError::TrailingGarbage { position });
         }
-        _ => Err(format!("unrecognized type id: {}", header.type_id)),
     }
+    Ok(Ast(entry))
 }
 
-fn update_remaining_total_length(open: &mut [(Header, Length, Vec<Entry>)], consumed: usize) {
-    open.iter_mut()
-        .for_each(|(_, remaining, _)| match remaining {
-            Length::TotalLength(len) => *len -= consumed,
-            Length::NumberOfSubPackets(_) => {}
-        });
+/// Collects bits most significant first.
+#[derive(Debug, Clone, Default)]
+struct BitWriter {
+    bits: Vec<bool>,
 }
 
-fn close_packets(open: &mut Vec<(Header, Length, Vec<Entry>)>) -> Result<Option<Entry>, String> {
-    while let Some((header, mut remaining, data)) = open.pop() {
-        let end_of_packet = match &mut remaining {
-            Length::TotalLength(len) => *len == 0,
-            Length::NumberOfSubPackets(num) => {
-                *num -= 1;
-                *num == 0
-            }
-        };
-        if end_of_packet {
-            let operation = decode_operation(header, data)?;
-            if let Some((_, _, parent)) = open.last_mut() {
-                parent.push(operation);
-            } else {
-                return Ok(Some(operation));
-            }
-        } else {
-            open.push((header, remaining, data));
-            break;
-        }
+impl BitWriter {
+    fn write_bits(&mut self, value: u64, n: usize) {
+        self.bits
+            .extend((0..n).rev().map(|shift| value >> shift & 1 == 1));
+    }
+
+    fn append(&mut self, other: BitWriter) {
+        self.bits.extend(other.bits);
+    }
+
+    /// Returns the bits as hexadecimal string padded with zeros to whole
+    /// bytes.
+    fn to_hex(&self) -> String {
+        self.bits
+            .chunks(8)
+            .map(|byte| {
+                let value = byte
+                    .iter()
+                    .chain(std::iter::repeat(&false))
+                    .take(8)
+                    .fold(0u8, |acc, bit| acc << 1 | *bit as u8);
+                format!("{:02X}", value)
+            })
+            .collect()
     }
-    Ok(None)
 }
 
-pub fn decode_message(message: &str) -> Result<Ast, String> {
-    let mut open: Vec<(Header, Length, Vec<Entry>)> = Vec::new();
-    let mut input = message;
-    while input.len() > 10 {
-        let (header, rest, consumed1) = decode_header(input);
-        match header.type_id {
-            4 => {
-                let (number, rest, consumed2) = decode_number(rest);
-                input = rest;
-                update_remaining_total_length(&mut open, consumed1 + consumed2);
-                if let Some((_, _, data)) = open.last_mut() {
-                    data.push(Entry::Number(number));
-                }
-                if let Some(root) = close_packets(&mut open)? {
-                    return Ok(Ast(root));
-                }
+fn encode_entry(entry: &Entry, writer: &mut BitWriter) -> Result<(), String> {
+    if entry.version > 7 {
+        return Err(format!(
+            "version {} does not fit into 3 bits",
+            entry.version
+        ));
+    }
+    writer.write_bits(entry.version as u64, 3);
+    writer.write_bits(entry.operation.type_id() as u64, 3);
+    if let Operation::Number(number) = entry.operation {
+        if number < 0 {
+            return Err(format!("negative number {} can not be encoded", number));
+        }
+        let number = number as u64;
+        let num_groups = (64 - number.leading_zeros() as usize).div_ceil(4);
+        for group in (0..num_groups.max(1)).rev() {
+            writer.write_bits((group > 0) as u64, 1);
+            writer.write_bits(number >> (group * 4) & 0xF, 4);
+        }
+        return Ok(());
+    }
+    let operands = entry.operation.operands();
+    if operands.is_empty() {
+        return Err(format!(
+            "operator of type {} without operands",
+            entry.operation.type_id()
+        ));
+    }
+    match entry.length_type.unwrap_or(LengthType::TotalLength) {
+        LengthType::TotalLength => {
+            let mut sub_packets = BitWriter::default();
+            for operand in operands {
+                encode_entry(operand, &mut sub_packets)?;
             }
-            _ => {
-                let (length, rest, consumed2) = decode_length(rest);
-                input = rest;
-                update_remaining_total_length(&mut open, consumed1 + consumed2);
-                open.push((header, length, Vec::new()));
+            if sub_packets.bits.len() >= 1 << 15 {
+                return Err("sub-packets too long for 15 bit total length".into());
             }
-        }
+            writer.write_bits(0, 1);
+            writer.write_bits(sub_packets.bits.len() as u64, 15);
+            writer.append(sub_packets);
+        },
+        LengthType::NumberOfSubPackets => {
+            if operands.len() >= 1 << 11 {
+                return Err("too many sub-packets for 11 bit count".into());
+            }
+            writer.write_bits(1, 1);
+            writer.write_bits(operands.len() as u64, 11);
+            for operand in operands {
+                encode_entry(operand, writer)?;
+            }
+        },
     }
-    Err(format!("no operation in message"))
+    Ok(())
+}
+
+/// Encodes the tree as a hexadecimal BITS transmission that decodes back into
+/// the same tree.
+pub fn encode_message(ast: &Ast) -> Result<String, String> {
+    let mut writer = BitWriter::default();
+    encode_entry(&ast.0, &mut writer)?;
+    Ok(writer.to_hex())
 }
 
-fn evaluate_operation(operator: &Entry, params: &[i64]) -> i64 {
+fn evaluate_operation(operator: &Operation, params: &[i64]) -> i64 {
     match operator {
-        Entry::Number(_) => unreachable!(),
-        Entry::Sum(_) => params.iter().copied().sum(),
-        Entry::Product(_) => params.iter().copied().product(),
-        Entry::Minimum(_) => params.iter().copied().min().unwrap_or(0),
-        Entry::Maximum(_) => params.iter().copied().max().unwrap_or(0),
-        Entry::GreaterThan(_, _) => {
+        Operation::Number(_) => unreachable!(),
+        Operation::Sum(_) => params.iter().copied().sum(),
+        Operation::Product(_) => params.iter().copied().product(),
+        Operation::Minimum(_) => params.iter().copied().min().unwrap_or(0),
+        Operation::Maximum(_) => params.iter().copied().max().unwrap_or(0),
+        Operation::GreaterThan(_, _) => {
             if params[0] > params[1] {
                 1
             } else {
                 0
             }
-        }
-        Entry::LessThan(_, _) => {
+        },
+        Operation::LessThan(_, _) => {
             if params[0] < params[1] {
                 1
             } else {
                 0
             }
-        }
-        Entry::EqualTo(_, _) => {
+        },
+        Operation::EqualTo(_, _) => {
             if params[0] == params[1] {
                 1
             } else {
                 0
             }
-        }
+        },
     }
 }
 
 pub fn evaluate_ast(ast: &Ast) -> i64 {
-    let mut open: Vec<(&Entry, Option<usize>)> = Vec::new();
-    let mut closed: Vec<(&Entry, Option<usize>, Vec<i64>)> = Vec::new();
+    // every operand is evaluated into its own slot of the parameters of the
+    // parent, so the order of evaluation does not matter
+    type Slot = Option<(usize, usize)>;
+    let mut open: Vec<(&Entry, Slot)> = Vec::new();
+    let mut closed: Vec<(&Operation, Slot, Vec<i64>)> = Vec::new();
     open.push((&ast.0, None));
-    while let Some((entry, parent)) = open.pop() {
-        match entry {
-            Entry::Number(number) => {
-                if let Some(index) = parent {
-                    closed[index].2.push(*number);
+    while let Some((entry, slot)) = open.pop() {
+        match &entry.operation {
+            Operation::Number(number) => {
+                if let Some((index, position)) = slot {
+                    closed[index].2[position] = *number;
                 } else {
                     return *number;
                 }
-            }
-            Entry::Sum(entries) => {
-                let current = Some(closed.len());
-                open.extend(entries.iter().rev().map(|e| (e, current)));
-                closed.push((entry, parent, Vec::new()));
-            }
-            Entry::Product(entries) => {
-                let current = Some(closed.len());
-                open.extend(entries.iter().rev().map(|e| (e, current)));
-                closed.push((entry, parent, Vec::new()));
-            }
-            Entry::Minimum(entries) => {
-                let current = Some(closed.len());
-                open.extend(entries.iter().rev().map(|e| (e, current)));
-                closed.push((entry, parent, Vec::new()));
-            }
-            Entry::Maximum(entries) => {
-                let current = Some(closed.len());
-                open.extend(entries.iter().rev().map(|e| (e, current)));
-                closed.push((entry, parent, Vec::new()));
-            }
-            Entry::GreaterThan(entry1, entry2) => {
-                let current = Some(closed.len());
-                open.push((entry2, current));
-                open.push((entry1, current));
-                closed.push((entry, parent, Vec::new()));
-            }
-            Entry::LessThan(entry1, entry2) => {
-                let current = Some(closed.len());
-                open.push((entry2, current));
-                open.push((entry1, current));
-                closed.push((entry, parent, Vec::new()));
-            }
-            Entry::EqualTo(entry1, entry2) => {
-                let current = Some(closed.len());
-                open.push((entry2, current));
-                open.push((entry1, current));
-                closed.push((entry, parent, Vec::new()));
-            }
+            },
+            operation => {
+                let current = closed.len();
+                let operands = operation.operands();
+                closed.push((operation, slot, vec![0; operands.len()]));
+                open.extend(
+                    operands
+                        .into_iter()
+                        .enumerate()
+                        .map(|(position, operand)| (operand, Some((current, position)))),
+                );
+            },
         }
     }
-    while let Some((operator, parent, params)) = closed.pop() {
+    // operators are closed after their parents, so popping them evaluates
+    // all operands before their operator
+    while let Some((operator, slot, params)) = closed.pop() {
         let result = evaluate_operation(operator, &params);
-        if let Some(index) = parent {
-            closed[index].2.insert(0, result);
+        if let Some((index, position)) = slot {
+            closed[index].2[position] = result;
         } else {
             return result;
         }
@@ -566,8 +832,8 @@ pub fn evaluate_ast(ast: &Ast) -> i64 {
 }
 
 #[aoc(day16, part2)]
-pub fn solve_part2(message: &str) -> i64 {
-    let ast = decode_message(message).unwrap_or_else(|err| panic!("{}", err));
+pub fn solve_part2(message: &[u8]) -> i64 {
+    let ast = decode_message(message).unwrap_or_else(|err| panic!("{:?}", err));
     evaluate_ast(&ast)
 }
 
@@ -2,6 +2,241 @@ use super::*;
 
 const INPUT: &str = include_str!("../../../input/2021/day16.txt");
 
+mod ast {
+    use super::*;
+
+    #[test]
+    fn decode_literal_value() {
+        let ast = decode_message(&parse("D2FE28")).unwrap();
+
+        assert_eq!(
+            ast,
+            Ast(Entry {
+                version: 6,
+                length_type: None,
+                operation: Operation::Number(2021)
+            })
+        );
+    }
+
+    #[test]
+    fn decode_keeps_versions_and_length_types() {
+        let ast = decode_message(&parse("EE00D40C823060")).unwrap();
+
+        assert_eq!(
+            ast,
+            Ast(Entry {
+                version: 7,
+                length_type: Some(LengthType::NumberOfSubPackets),
+                operation: Operation::Maximum(vec![
+                    Entry::number(1).with_version(2),
+                    Entry::number(2).with_version(4),
+                    Entry::number(3).with_version(1),
+                ])
+            })
+        );
+        assert_eq!(
+            ast.0.header(),
+            Header {
+                version: 7,
+                type_id: 3
+            }
+        );
+        assert_eq!(ast.0.version_sum(), 14);
+    }
+
+    #[test]
+    fn encode_round_trips_examples() {
+        for hex in [
+            "D2FE28",
+            "38006F45291200",
+            "EE00D40C823060",
+            "8A004A801A8002F478",
+            "620080001611562C8802118E34",
+            "C0015000016115A2E0802F182340",
+            "A0016C880162017C3686B18A3D4780",
+            "9C0141080250320F1802104A08",
+        ] {
+            let ast = decode_message(&parse(hex)).unwrap();
+
+            let encoded = encode_message(&ast).unwrap();
+
+            assert_eq!(decode_message(&parse(&encoded)), Ok(ast));
+        }
+    }
+
+    #[test]
+    fn encode_literal_value() {
+        let ast = Ast(Entry::number(2021).with_version(6));
+
+        assert_eq!(encode_message(&ast), Ok("D2FE28".into()));
+    }
+
+    #[test]
+    fn encode_operator_with_number_of_sub_packets() {
+        let ast = Ast(Entry::new(Operation::Maximum(vec![
+            Entry::number(1).with_version(2),
+            Entry::number(2).with_version(4),
+            Entry::number(3).with_version(1),
+        ]))
+        .with_version(7)
+        .with_length_type(LengthType::NumberOfSubPackets));
+
+        assert_eq!(encode_message(&ast), Ok("EE00D40C823060".into()));
+    }
+
+    #[test]
+    fn encode_rejects_unencodable_values() {
+        assert!(encode_message(&Ast(Entry::number(-1))).is_err());
+        assert!(encode_message(&Ast(Entry::number(1).with_version(8))).is_err());
+        assert!(encode_message(&Ast(Entry::new(Operation::Sum(Vec::new())))).is_err());
+        assert!(encode_message(&Ast(Entry::new(Operation::Maximum(Vec::new())))).is_err());
+    }
+
+    #[test]
+    fn generate_transmission_from_expression() {
+        let ast = "(+ 1 (* 2 3) (min 7 4 9) (= (max 1 5) (< 3 4)))"
+            .parse::<Ast>()
+            .unwrap();
+
+        let transmission = encode_message(&ast).unwrap();
+        let decoded = decode_message(&parse(&transmission)).unwrap();
+
+        assert_eq!(decoded, ast);
+        assert_eq!(evaluate_ast(&decoded), 11);
+    }
+
+    #[test]
+    fn evaluate_mixed_literal_and_operator_operands_after_round_trip() {
+        for (expression, value) in [
+            ("(< 5 (+ 1 2))", 0),
+            ("(< (+ 1 2) 5)", 1),
+            ("(> 5 (+ 1 2))", 1),
+            ("(> (+ 1 2) 5)", 0),
+            ("(= 3 (+ 1 2))", 1),
+            ("(= (+ 1 2) 4)", 0),
+            ("(< 1 (> 2 (min 1 (+ 2 3))))", 0),
+        ] {
+            let ast = expression.parse::<Ast>().unwrap();
+
+            let transmission = encode_message(&ast).unwrap();
+            let decoded = decode_message(&parse(&transmission)).unwrap();
+
+            assert_eq!(decoded, ast, "{}", expression);
+            assert_eq!(evaluate_ast(&decoded), value, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn display_as_s_expression() {
+        let ast = decode_message(&parse("9C0141080250320F1802104A08")).unwrap();
+
+        assert_eq!(ast.to_string(), "(= (+ 1 3) (* 2 2))");
+        assert_eq!(format!("{:#}", ast), "(=\n  (+ 1 3)\n  (* 2 2))");
+        assert_eq!(
+            ast.to_string().parse::<Ast>().map(|ast| evaluate_ast(&ast)),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn parse_invalid_expressions() {
+        assert!("(> 1)".parse::<Ast>().is_err());
+        assert!("(+)".parse::<Ast>().is_err());
+        assert!("(*)".parse::<Ast>().is_err());
+        assert!("(min)".parse::<Ast>().is_err());
+        assert!("(max)".parse::<Ast>().is_err());
+        assert!("(+ 1 2".parse::<Ast>().is_err());
+        assert!("(^ 1 2)".parse::<Ast>().is_err());
+        assert!("1 2".parse::<Ast>().is_err());
+        assert!("".parse::<Ast>().is_err());
+    }
+}
+
+mod bit_reader {
+    use super::*;
+
+    #[test]
+    fn read_bits_across_bytes() {
+        let bytes = parse("D2FE28");
+        let mut reader = BitReader::new(&bytes);
+
+        assert_eq!(reader.read_bits(3), Ok(6));
+        assert_eq!(reader.read_bits(3), Ok(4));
+        assert_eq!(reader.read_bits(5), Ok(0b10111));
+        assert_eq!(reader.position(), 11);
+        assert_eq!(reader.read_bits(13), Ok(0b1111000101000));
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(
+            reader.read_bit(),
+            Err(DecodeError::Truncated { position: 24 })
+        );
+    }
+
+    #[test]
+    fn parse_odd_number_of_digits() {
+        assert_eq!(parse("ABC"), vec![0xAB, 0xC0]);
+    }
+
+    #[test]
+    fn decode_truncated_packet() {
+        let result = decode_message(&parse("D2FE"));
+
+        assert_eq!(result, Err(DecodeError::Truncated { position: 16 }));
+    }
+
+    #[test]
+    fn decode_truncated_total_length() {
+        let result = decode_message(&parse("38006F452912"));
+
+        assert_eq!(result, Err(DecodeError::Truncated { position: 22 }));
+    }
+
+    #[test]
+    fn decode_trailing_garbage() {
+        let result = decode_message(&parse("D2FE29"));
+
+        assert_eq!(result, Err(DecodeError::TrailingGarbage { position: 23 }));
+    }
+
+    #[test]
+    fn decode_sub_packets_exceeding_total_length() {
+        // sum with total length 10 containing a literal of 11 bits
+        let result = decode_message(&parse("0000284080"));
+
+        assert_eq!(result, Err(DecodeError::LengthMismatch { position: 0 }));
+    }
+
+    #[test]
+    fn decode_comparison_with_three_operands() {
+        let ast = Ast(Entry::new(Operation::Sum(vec![
+            Entry::number(1),
+            Entry::number(2),
+            Entry::number(3),
+        ])));
+        let mut bytes = parse(&encode_message(&ast).unwrap());
+        // change type id from sum (0) to greater than (5)
+        bytes[0] |= 0b0001_0100;
+
+        let result = decode_message(&bytes);
+
+        assert_eq!(
+            result,
+            Err(DecodeError::InvalidOperands {
+                position: 0,
+                type_id: 5
+            })
+        );
+    }
+
+    #[test]
+    fn decode_number_too_large() {
+        let result = decode_message(&parse("13FFFFFFFFFFFFFFFFFFFDE0"));
+
+        assert_eq!(result, Err(DecodeError::NumberTooLarge { position: 6 }));
+    }
+}
+
 mod part1 {
     use super::*;
 